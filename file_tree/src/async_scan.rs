@@ -0,0 +1,182 @@
+use crate::io_limit::{self, IoPermit};
+use crate::ripignore::{IgnoreFile, IGNORE_FILE_NAME};
+use crate::{is_hidden_or_junk, natural_cmp, normalize_path, FileTree, FileTreeError};
+use std::future::Future;
+use std::panic;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Directories read at the same time by `from_directory_async`
+const DEFAULT_CONCURRENCY: usize = 16;
+
+impl FileTree {
+    /// Scans the directory at `path` like `try_from_directory(path, None, &[], false)`,
+    /// reading it with `tokio::fs` so that a large scan does not block the executor.
+    /// Subdirectories are scanned on tasks of the current runtime, at most 16
+    /// directories being read at a time. Like any scan, its filesystem operations
+    /// count against the `set_max_concurrent_io` limit.
+    /// `.ripignore` files are honored, see `IGNORE_FILE_NAME`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside of a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FileTreeError` naming the first directory or entry that could not be
+    /// read.
+    pub async fn from_directory_async(path: String) -> Result<FileTree, FileTreeError> {
+        let path = normalize_path(path).display().to_string();
+        let permits = Arc::new(Semaphore::new(DEFAULT_CONCURRENCY));
+        scan_directory(path, permits, Vec::new()).await
+    }
+}
+
+type Scan = Pin<Box<dyn Future<Output = Result<FileTree, FileTreeError>> + Send>>;
+
+// Boxed so that it can spawn itself for each subdirectory. `ignores` holds the
+// `.ripignore` files of the directories above `path`.
+fn scan_directory(
+    path: String,
+    permits: Arc<Semaphore>,
+    mut ignores: Vec<Arc<IgnoreFile>>,
+) -> Scan {
+    Box::pin(async move {
+        let entries = {
+            let _permit = permits
+                .acquire()
+                .await
+                .expect("the semaphore is never closed");
+            read_directory(&path, &mut ignores).await?
+        };
+
+        // Sorted like the ones of a blocking scan, spawned all at once and awaited in
+        // order so that directories keep their order
+        let mut dirs = entries.directories;
+        dirs.sort_by(|a, b| natural_cmp(a, b));
+        let scans: Vec<_> = dirs
+            .into_iter()
+            .map(|directory| {
+                tokio::spawn(scan_directory(directory, permits.clone(), ignores.clone()))
+            })
+            .collect();
+        let mut directories = Vec::with_capacity(scans.len());
+        for scan in scans {
+            let directory = scan
+                .await
+                .unwrap_or_else(|error| panic::resume_unwind(error.into_panic()))?;
+            directories.push(directory);
+        }
+
+        Ok(FileTree {
+            path,
+            files: entries.files,
+            sizes: entries.sizes,
+            directories,
+        })
+    })
+}
+
+/// The kept entries of a directory
+struct Entries {
+    files: Vec<String>,
+    sizes: Vec<u64>,
+    directories: Vec<String>,
+}
+
+// Lists the directory at `path`, skipping hidden and junk entries, symbolic links and
+// the entries matching `ignores`, to which the `.ripignore` of the directory is added
+async fn read_directory(
+    path: &str,
+    ignores: &mut Vec<Arc<IgnoreFile>>,
+) -> Result<Entries, FileTreeError> {
+    let contents = {
+        let _permit = acquire().await;
+        tokio::fs::read_to_string(Path::new(path).join(IGNORE_FILE_NAME)).await
+    };
+    if let Some(ignore) = IgnoreFile::from_contents(path, contents) {
+        ignores.push(Arc::new(ignore));
+    }
+
+    let mut read_dir = {
+        let _permit = acquire().await;
+        tokio::fs::read_dir(path).await
+    }
+    .map_err(|error| FileTreeError::io(path, error))?;
+    let mut entries = Entries {
+        files: Vec::new(),
+        sizes: Vec::new(),
+        directories: Vec::new(),
+    };
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|error| FileTreeError::io(path, error))?
+    {
+        let entry = entry.path().display().to_string();
+        if is_hidden_or_junk(&entry) || ignores.iter().any(|ignore| ignore.is_match(&entry)) {
+            continue;
+        }
+        let metadata = {
+            let _permit = acquire().await;
+            tokio::fs::symlink_metadata(&entry).await
+        }
+        .map_err(|error| FileTreeError::io(&entry, error))?;
+        if metadata.is_symlink() {
+            log::debug!("Skipping symbolic link {}", entry);
+        } else if metadata.is_dir() {
+            entries.directories.push(entry);
+        } else {
+            entries.sizes.push(metadata.len());
+            entries.files.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+// Takes a slot of the `set_max_concurrent_io` limit, waiting for one on a blocking
+// thread so that the executor is not held up
+async fn acquire() -> IoPermit {
+    match io_limit::try_acquire() {
+        Some(permit) => permit,
+        None => tokio::task::spawn_blocking(io_limit::acquire)
+            .await
+            .unwrap_or_else(|error| panic::resume_unwind(error.into_panic())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_async_scan_waits_for_the_global_limit() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("a.mp4"), "").unwrap();
+        let path = root.path().display().to_string();
+        let _limit = io_limit::TEST_LIMIT.lock().unwrap();
+        io_limit::set_max_concurrent_io(1);
+        let permit = io_limit::acquire();
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap();
+            let _ = sender.send(runtime.block_on(FileTree::from_directory_async(path)));
+        });
+        let waiting = receiver.recv_timeout(Duration::from_millis(200)).is_err();
+        drop(permit);
+        let tree = receiver.recv_timeout(Duration::from_secs(10));
+        io_limit::set_max_concurrent_io(0);
+
+        assert!(waiting);
+        assert_eq!(tree.unwrap().unwrap().count_files(), 1);
+    }
+}
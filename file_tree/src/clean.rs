@@ -0,0 +1,108 @@
+use crate::{io_limit, FileTreeError};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Number of entries removed by `clean_links`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct CleanSummary {
+    pub links: usize,
+    pub directories: usize,
+}
+
+/// Removes the symbolic links found below `destination`, then the subdirectories
+/// left empty. Regular files are never touched, so a directory holding one is kept.
+/// Every removed path is logged at the info level.
+///
+/// # Arguments
+///
+/// * `destination` - The directory to clean, usually the destination of a previous
+///   `create_grouped_symlinks` run. It is kept even when it ends up empty.
+/// * `dangling_only` - Only remove the links whose target no longer exists.
+///
+/// # Errors
+///
+/// Returns a `FileTreeError` naming the first entry that could not be read or removed.
+pub fn clean_links(destination: &Path, dangling_only: bool) -> Result<CleanSummary, FileTreeError> {
+    let mut summary = CleanSummary::default();
+    clean_directory(destination, dangling_only, &mut summary)?;
+    Ok(summary)
+}
+
+// Returns whether `directory` is empty once cleaned
+fn clean_directory(
+    directory: &Path,
+    dangling_only: bool,
+    summary: &mut CleanSummary,
+) -> Result<bool, FileTreeError> {
+    let io_error = |path: &Path| {
+        let path = path.display().to_string();
+        move |error| FileTreeError::io(path, error)
+    };
+
+    let entries = {
+        let _permit = io_limit::acquire();
+        fs::read_dir(directory)
+    }
+    .map_err(io_error(directory))?;
+
+    let mut empty = true;
+    for entry in entries {
+        let path = entry.map_err(io_error(directory))?.path();
+        let metadata = {
+            let _permit = io_limit::acquire();
+            fs::symlink_metadata(&path)
+        }
+        .map_err(io_error(&path))?;
+
+        if metadata.file_type().is_symlink() {
+            let target_exists = || {
+                let _permit = io_limit::acquire();
+                path.exists()
+            };
+            if dangling_only && target_exists() {
+                empty = false;
+                continue;
+            }
+            {
+                let _permit = io_limit::acquire();
+                remove_link(&path, &metadata)
+            }
+            .map_err(io_error(&path))?;
+            log::info!("Removed link {}", path.display());
+            summary.links += 1;
+        } else if metadata.is_dir() && clean_directory(&path, dangling_only, summary)? {
+            {
+                let _permit = io_limit::acquire();
+                fs::remove_dir(&path)
+            }
+            .map_err(io_error(&path))?;
+            log::info!("Removed directory {}", path.display());
+            summary.directories += 1;
+        } else {
+            empty = false;
+        }
+    }
+
+    Ok(empty)
+}
+
+/// Removes the symbolic link at `path`.
+#[cfg(not(windows))]
+fn remove_link(path: &Path, _metadata: &fs::Metadata) -> io::Result<()> {
+    fs::remove_file(path)
+}
+
+/// Removes the symbolic link at `path`. Windows removes links to directories like
+/// directories, whether their target still exists or not.
+#[cfg(windows)]
+fn remove_link(path: &Path, metadata: &fs::Metadata) -> io::Result<()> {
+    use std::os::windows::fs::FileTypeExt;
+
+    if metadata.file_type().is_symlink_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{io_limit, natural_cmp, FileTree};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use xxhash_rust::xxh3::Xxh3;
+
+// Bytes hashed at each end of a file, so multi-gigabyte videos are compared
+// without being read entirely
+const SAMPLE_SIZE: u64 = 1024 * 1024;
+
+impl FileTree {
+    /// Groups the files of the whole tree that hold the same content. Files are
+    /// compared by size, then by a hash of their first and last megabyte, which
+    /// is streamed from disk.
+    ///
+    /// # Returns
+    ///
+    /// * The groups of duplicates, each holding at least two files in natural order.
+    ///   Groups are ordered by their first file. Files that cannot be read are
+    ///   left out.
+    pub fn find_duplicates(&self) -> Vec<Vec<String>> {
+        let mut by_size: HashMap<u64, Vec<&str>> = HashMap::new();
+        for file in self.iter_files() {
+            let metadata = {
+                let _permit = io_limit::acquire();
+                fs::metadata(file)
+            };
+            match metadata {
+                Ok(metadata) => by_size.entry(metadata.len()).or_default().push(file),
+                Err(error) => log::warn!("Cannot read {}: {}", file, error),
+            }
+        }
+
+        let mut by_hash: HashMap<(u64, u64), Vec<&str>> = HashMap::new();
+        for (size, files) in by_size {
+            if files.len() < 2 {
+                continue;
+            }
+            for file in files {
+                match sample_hash(file, size) {
+                    Ok(hash) => by_hash.entry((size, hash)).or_default().push(file),
+                    Err(error) => log::warn!("Cannot read {}: {}", file, error),
+                }
+            }
+        }
+
+        let mut duplicates: Vec<Vec<String>> = by_hash
+            .into_values()
+            .filter(|files| files.len() > 1)
+            .map(|mut files| {
+                files.sort_by(|a, b| natural_cmp(a, b));
+                files.into_iter().map(String::from).collect()
+            })
+            .collect();
+        duplicates.sort_by(|a, b| natural_cmp(&a[0], &b[0]));
+        duplicates
+    }
+}
+
+/// Hashes the first and last `SAMPLE_SIZE` bytes of the file at `path`, which
+/// is `size` bytes long.
+fn sample_hash(path: &str, size: u64) -> io::Result<u64> {
+    let _permit = io_limit::acquire();
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    let mut hash_range = |file: &mut File, start: u64, length: u64| -> io::Result<()> {
+        file.seek(SeekFrom::Start(start))?;
+        let mut remaining = length;
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len() as u64) as usize;
+            file.read_exact(&mut buffer[..chunk])?;
+            hasher.update(&buffer[..chunk]);
+            remaining -= chunk as u64;
+        }
+        Ok(())
+    };
+
+    hash_range(&mut file, 0, size.min(SAMPLE_SIZE))?;
+    if size > SAMPLE_SIZE {
+        let tail = (size - SAMPLE_SIZE).min(SAMPLE_SIZE);
+        hash_range(&mut file, size - tail, tail)?;
+    }
+    Ok(hasher.digest())
+}
+
+/// Hashes the whole content of the file at `path`, streaming it from disk.
+pub(crate) fn full_hash(path: &Path) -> io::Result<u64> {
+    let _permit = io_limit::acquire();
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => return Ok(hasher.digest()),
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+}
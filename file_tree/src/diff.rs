@@ -0,0 +1,53 @@
+use crate::{natural_cmp, FileTree};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Files added and removed between two trees, as returned by `diff`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TreeDiff {
+    /// Paths of the files only in the new tree, in natural order
+    pub added: Vec<String>,
+    /// Paths of the files only in the old tree, in natural order
+    pub removed: Vec<String>,
+}
+
+impl TreeDiff {
+    /// Whether both trees hold the same files.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Formats the diff as a one-line summary, e.g. `3 files added, 1 removed`.
+impl fmt::Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} files added, {} removed",
+            self.added.len(),
+            self.removed.len()
+        )
+    }
+}
+
+/// Compares the files of `old`, e.g. a tree saved by the `json` subcommand, with the
+/// ones of `new`, usually a fresh scan of the same directory. Files are matched by
+/// full path, so both trees should be read from the same path; a file moved to
+/// another directory is both removed and added. Directories and sizes are ignored.
+pub fn diff(old: &FileTree, new: &FileTree) -> TreeDiff {
+    let old_files: HashSet<&str> = old.iter_files().collect();
+    let new_files: HashSet<&str> = new.iter_files().collect();
+    let only_in = |files: &HashSet<&str>, other: &HashSet<&str>| {
+        let mut only: Vec<String> = files
+            .difference(other)
+            .map(|file| file.to_string())
+            .collect();
+        only.sort_by(|a, b| natural_cmp(a, b));
+        only
+    };
+    TreeDiff {
+        added: only_in(&new_files, &old_files),
+        removed: only_in(&old_files, &new_files),
+    }
+}
@@ -0,0 +1,95 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Words marking a special (Plex's Season 00) in a file or directory name, see
+/// `LinkOptions::specials`
+pub const DEFAULT_SPECIALS_KEYWORDS: [&str; 6] =
+    ["special", "specials", "ova", "oad", "extra", "extras"];
+
+// `S01E02`, `s1.e2`, `S01 E02`
+fn season_episode_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)(?:^|[^a-z0-9])s(\d{1,3})[ ._-]?e(\d{1,4})(?:[^0-9]|$)").unwrap()
+    })
+}
+
+// `1x02`, `01x002`
+fn cross_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?i)(?:^|[^a-z0-9])(\d{1,2})x(\d{2,3})(?:[^0-9]|$)").unwrap())
+}
+
+// `Episode 2`, `episode_02`
+fn episode_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)(?:^|[^a-z0-9])episode[ ._-]*(\d{1,4})(?:[^0-9]|$)").unwrap()
+    })
+}
+
+// `part1`, `Part 2`, `pt.3`, `CD1`
+fn part_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)(?:^|[^a-z0-9])((?:part|pt|cd)[ ._]?(\d{1,2}))(?:[^0-9]|$)").unwrap()
+    })
+}
+
+/// Extracts the season and episode numbers from a file name.
+///
+/// Recognizes `S01E02`, `1x02` and `Episode 2` (which carries no season and is
+/// reported as season 1).
+///
+/// # Returns
+///
+/// * `Some((season, episode))` when a pattern matched, `None` otherwise.
+pub fn parse_season_episode(name: &str) -> Option<(u32, u32)> {
+    parse_episode_numbers(name).map(|(season, episode)| (season.unwrap_or(1), episode))
+}
+
+/// Like `parse_season_episode`, but leaves the season empty when the name only
+/// carries an episode number so the caller can supply it.
+pub(crate) fn parse_episode_numbers(name: &str) -> Option<(Option<u32>, u32)> {
+    for regex in [season_episode_regex(), cross_regex()] {
+        if let Some(captures) = regex.captures(name) {
+            let season = captures[1].parse().ok()?;
+            let episode = captures[2].parse().ok()?;
+            return Some((Some(season), episode));
+        }
+    }
+
+    let captures = episode_regex().captures(name)?;
+    Some((None, captures[1].parse().ok()?))
+}
+
+/// Detects the part number of a multi-part file such as `Episode - part1.mkv`,
+/// `Movie pt.2.avi` or `Film CD1.mkv`. The marker has to follow a title: files
+/// named just `Part 1`, `Part 2`, ... are episodes of their own.
+///
+/// # Returns
+///
+/// * `Some((rest, part))`, `rest` being the name without the part marker, so that
+///   the parts of one episode share it. `None` when the name has no part marker.
+pub fn split_part(name: &str) -> Option<(String, u32)> {
+    let captures = part_regex().captures(name)?;
+    let marker = captures.get(1)?;
+    if !name[..marker.start()].chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    let part = captures[2].parse().ok()?;
+    Some((
+        format!("{}{}", &name[..marker.start()], &name[marker.end()..]),
+        part,
+    ))
+}
+
+/// Whether `name` holds one of `keywords` as a whole word, ignoring case, e.g.
+/// `Show - OVA 2.mkv` or `Specials` for the keywords `ova` and `specials`.
+pub fn is_special(name: &str, keywords: &[String]) -> bool {
+    name.split(|c: char| !c.is_alphanumeric()).any(|word| {
+        keywords
+            .iter()
+            .any(|keyword| word.eq_ignore_ascii_case(keyword))
+    })
+}
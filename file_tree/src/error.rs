@@ -0,0 +1,94 @@
+use std::io;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors that can occur while building or processing a `FileTree`.
+#[derive(Debug, Error)]
+pub enum FileTreeError {
+    /// A filesystem operation on `path` failed.
+    #[error("failed to access {path}")]
+    Io {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// A tree was requested from an empty list of paths.
+    #[error("expected at least one path")]
+    EmptyInput,
+
+    /// The path at `index` of a list of paths is empty.
+    #[error("path {index} of the list is empty")]
+    EmptyPath { index: usize },
+
+    /// Reading a directory took longer than `ScanConfig::scan_timeout`, e.g. on a hung
+    /// network mount.
+    #[error("reading {path} timed out after {timeout:?}")]
+    ScanTimeout { path: String, timeout: Duration },
+
+    /// A scan found more files than `ScanConfig::max_files`, e.g. when pointed at `/`
+    /// by mistake. `path` is the directory being read when the limit was exceeded.
+    #[error("more than {limit} files found, stopped scanning at {path}")]
+    TooManyFiles { limit: usize, path: String },
+
+    /// A glob pattern could not be compiled.
+    #[error("invalid glob pattern {pattern}")]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+
+    /// A regular expression could not be compiled.
+    #[error("invalid regular expression {pattern}")]
+    InvalidRegex {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+
+    /// A rename pattern is not written `regex=>replacement`.
+    #[error("invalid rename pattern {text}, expected `regex=>replacement`")]
+    InvalidRenamePattern { text: String },
+
+    /// A grouping pattern has no capture group named `group`.
+    #[error("regular expression {pattern} has no capture group named `group`")]
+    MissingGroupCapture { pattern: String },
+
+    /// A naming template has an unknown placeholder or unbalanced braces.
+    #[error("invalid naming template {template}: {reason}")]
+    InvalidTemplate { template: String, reason: String },
+
+    /// Link options that cannot be combined were set together.
+    #[error("invalid link options: {reason}")]
+    InvalidOptions { reason: String },
+
+    /// The link destination is the source directory, or one is nested in the other.
+    #[error("destination {destination} overlaps the source directory {base}")]
+    OverlappingPaths { base: String, destination: String },
+
+    /// A file of the tree to link no longer exists.
+    #[error("source file {path} no longer exists")]
+    MissingSource { path: String },
+
+    /// A link or copy created by a run does not match its source.
+    #[error("verification of {path} failed: {reason}")]
+    VerificationFailed { path: String, reason: String },
+
+    /// A size is not a number followed by an optional unit such as `MB` or `GiB`.
+    #[error("invalid size {text}, expected e.g. 700MB or 1.5GiB")]
+    InvalidSize { text: String },
+
+    /// A date is neither an ISO-8601 date (and time) nor a relative time such as `7d`.
+    #[error("invalid date {text}, expected e.g. 2024-05-01, 2024-05-01T18:30:00Z or 7d")]
+    InvalidDate { text: String },
+}
+
+impl FileTreeError {
+    pub(crate) fn io(path: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+// Maximum number of filesystem operations allowed in flight (0 = unlimited)
+static MAX_CONCURRENT_IO: AtomicUsize = AtomicUsize::new(0);
+static IN_FLIGHT: Mutex<usize> = Mutex::new(0);
+static RELEASED: Condvar = Condvar::new();
+
+thread_local! {
+    // Set on the worker threads of timed reads, whose permit is held by the thread
+    // waiting for them, see `exempt_current_thread`
+    static EXEMPT: Cell<bool> = const { Cell::new(false) };
+}
+
+// Permits acquired by the current thread, to check which code touches the disk
+#[cfg(test)]
+thread_local! {
+    static ACQUIRED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Number of permits the current thread acquired so far.
+#[cfg(test)]
+pub(crate) fn acquired() -> usize {
+    ACQUIRED.with(|acquired| acquired.get())
+}
+
+/// Sets the global limit of filesystem operations that may be in flight at
+/// the same time. The limit is shared by every scan and link operation of the
+/// process, regardless of how many threads issue them.
+///
+/// There is no limit by default. Links are created one at a time and a scan
+/// has at most one operation in flight per scan thread (one per CPU, see
+/// `ScanConfig::threads`), which local disks and SSDs handle without slowing
+/// down. A limit pays off on network shares and spinning disks that throttle
+/// or seek under concurrent requests: start low (e.g. 2 to 4) and raise it
+/// while scans get faster.
+///
+/// # Arguments
+///
+/// * `limit` - The maximum number of concurrent operations. `0` disables the limit.
+pub fn set_max_concurrent_io(limit: usize) {
+    MAX_CONCURRENT_IO.store(limit, Ordering::SeqCst);
+    RELEASED.notify_all();
+}
+
+/// Returns the currently configured limit (`0` means unlimited).
+pub fn max_concurrent_io() -> usize {
+    MAX_CONCURRENT_IO.load(Ordering::SeqCst)
+}
+
+/// A permit for a single filesystem operation. The slot is released when the
+/// permit is dropped.
+pub(crate) struct IoPermit {
+    /// Whether the permit holds a slot, see `exempt_current_thread`
+    counted: bool,
+}
+
+/// Blocks until a filesystem operation slot is available and returns its permit.
+pub(crate) fn acquire() -> IoPermit {
+    #[cfg(test)]
+    ACQUIRED.with(|acquired| acquired.set(acquired.get() + 1));
+    if EXEMPT.with(Cell::get) {
+        return IoPermit { counted: false };
+    }
+
+    let mut in_flight = IN_FLIGHT.lock().unwrap();
+    loop {
+        let limit = max_concurrent_io();
+        if limit == 0 || *in_flight < limit {
+            break;
+        }
+        in_flight = RELEASED.wait(in_flight).unwrap();
+    }
+    *in_flight += 1;
+    IoPermit { counted: true }
+}
+
+/// Returns the permit of a filesystem operation slot when one is available right
+/// away, without blocking, and `None` otherwise.
+#[cfg(feature = "tokio")]
+pub(crate) fn try_acquire() -> Option<IoPermit> {
+    if EXEMPT.with(Cell::get) {
+        return Some(acquire());
+    }
+
+    let mut in_flight = IN_FLIGHT.lock().unwrap();
+    let limit = max_concurrent_io();
+    if limit != 0 && *in_flight >= limit {
+        return None;
+    }
+    *in_flight += 1;
+    #[cfg(test)]
+    ACQUIRED.with(|acquired| acquired.set(acquired.get() + 1));
+    Some(IoPermit { counted: true })
+}
+
+/// Lets the operations of the current thread run without a slot of their own,
+/// for a worker whose operations are covered by a permit held by the thread
+/// waiting for it. That permit is released when the waiting thread gives up, even
+/// if the worker is blocked for good.
+pub(crate) fn exempt_current_thread() {
+    EXEMPT.with(|exempt| exempt.set(true));
+}
+
+impl Drop for IoPermit {
+    fn drop(&mut self) {
+        if !self.counted {
+            return;
+        }
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        *in_flight -= 1;
+        RELEASED.notify_one();
+    }
+}
+
+// The limit is global, tests changing it run one at a time
+#[cfg(test)]
+pub(crate) static TEST_LIMIT: Mutex<()> = Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_at_most_limit_permits_are_held() {
+        let _limit = TEST_LIMIT.lock().unwrap();
+        set_max_concurrent_io(2);
+        let held = AtomicUsize::new(0);
+        let most_held = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        let _permit = acquire();
+                        let count = held.fetch_add(1, Ordering::SeqCst) + 1;
+                        most_held.fetch_max(count, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(1));
+                        held.fetch_sub(1, Ordering::SeqCst);
+                    }
+                });
+            }
+        });
+        set_max_concurrent_io(0);
+
+        assert!(most_held.into_inner() <= 2);
+    }
+
+    #[test]
+    fn test_exempt_thread_does_not_wait_for_a_slot() {
+        let _limit = TEST_LIMIT.lock().unwrap();
+        set_max_concurrent_io(1);
+        let permit = acquire();
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            exempt_current_thread();
+            let _permit = acquire();
+            sender.send(()).unwrap();
+        });
+        let result = receiver.recv_timeout(Duration::from_secs(10));
+        drop(permit);
+        set_max_concurrent_io(0);
+
+        assert!(result.is_ok());
+    }
+}
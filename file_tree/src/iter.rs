@@ -0,0 +1,45 @@
+use crate::FileTree;
+use std::slice;
+
+/// Lazy depth-first iterator over the file paths of a `FileTree`: the files of a
+/// directory come first, then the files of each of its subdirectories in turn.
+/// Returned by `FileTree::iter_files` and by iterating over a `&FileTree`.
+#[derive(Clone, Debug)]
+pub struct Files<'a> {
+    files: slice::Iter<'a, String>,
+    pending: Vec<&'a FileTree>,
+}
+
+impl<'a> Files<'a> {
+    pub(crate) fn new(tree: &'a FileTree) -> Self {
+        Self {
+            files: tree.files.iter(),
+            pending: tree.directories.iter().rev().collect(),
+        }
+    }
+}
+
+impl<'a> Iterator for Files<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        loop {
+            if let Some(file) = self.files.next() {
+                return Some(file);
+            }
+
+            let tree = self.pending.pop()?;
+            self.files = tree.files.iter();
+            self.pending.extend(tree.directories.iter().rev());
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a FileTree {
+    type Item = &'a str;
+    type IntoIter = Files<'a>;
+
+    fn into_iter(self) -> Files<'a> {
+        Files::new(self)
+    }
+}
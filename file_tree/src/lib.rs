@@ -1,26 +1,101 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::fs::{self, read_dir, ReadDir};
-use std::os::unix::fs::symlink;
-use std::path::Path;
+use std::fmt;
+use std::fs::{self, ReadDir};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-// Constant to store postfixes
-const POST_FIXES: [&str; 1] = [".mp4"];
+#[cfg(feature = "tokio")]
+mod async_scan;
+mod clean;
+mod dedupe;
+mod diff;
+mod episode;
+mod error;
+mod io_limit;
+mod iter;
+mod link;
+mod natural_sort;
+mod nfo;
+mod quality;
+mod rename;
+mod ripignore;
+mod scan_config;
+mod stats;
+mod string_vector;
+mod template;
+mod tree_parser;
+
+pub use clean::{clean_links, CleanSummary};
+pub use diff::{diff, TreeDiff};
+pub use episode::{is_special, parse_season_episode, split_part, DEFAULT_SPECIALS_KEYWORDS};
+pub use error::FileTreeError;
+pub use io_limit::{max_concurrent_io, set_max_concurrent_io};
+pub use iter::Files;
+pub use link::{
+    apply, apply_with_progress, check_disjoint, sanitize_link_name, ApplyReport, GroupBy,
+    GroupSort, LinkMapping, LinkMode, LinkOptions, PlannedAction, Progress, SymlinkReport,
+};
+pub use natural_sort::{natural_cmp, SortOrder};
+pub use rename::RenamePattern;
+pub use ripignore::IGNORE_FILE_NAME;
+pub use scan_config::{walk, ScanConfig};
+pub use stats::TreeStats;
+pub use template::{expand_destination, PLEX_GROUP_TEMPLATE};
+
+/// Path of the synthetic root holding the trees of several directories, see
+/// `FileTree::from_directories`. It names no directory on disk: linking such a tree
+/// checks and names each root instead, the first one naming the show.
+pub const MULTI_ROOT_PATH: &str = "<multiple roots>";
+
+/// Media file extensions used when none are given, see `default_extensions`
+pub const DEFAULT_MEDIA_EXTENSIONS: &[&str] = &[".mp4"];
+
+// Names of the metadata files and directories NAS boxes and operating systems
+// scatter around media libraries, skipped unless hidden entries are included
+const JUNK_NAMES: [&str; 8] = [
+    "@eaDir",
+    ".AppleDouble",
+    ".DS_Store",
+    "#recycle",
+    "$RECYCLE.BIN",
+    "System Volume Information",
+    "Thumbs.db",
+    "desktop.ini",
+];
 
 // Create an enum to store the grouping type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum GroupingType {
     Plex,
+    #[default]
     Original,
 }
 
-/// Represents a tree structure for files
-#[derive(Serialize, Deserialize, Debug)]
+/// Represents a tree structure for files. Paths are stored verbatim, as read from
+/// disk or given to `from_string_vector`: leading and trailing spaces are part of
+/// a name and are never trimmed.
+///
+/// `==` compares the entries in their stored order, see `structurally_eq` for a
+/// comparison that ignores it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct FileTree {
     pub path: String,
     pub files: Vec<String>,
+    /// Size in bytes of each entry of `files`, at the same index. Empty when the
+    /// tree was not read from disk.
+    #[serde(default)]
+    pub sizes: Vec<u64>,
     pub directories: Vec<FileTree>,
 }
 
+/// Files and directories found in a directory, with their metadata
+struct DirectoryEntries {
+    files: Vec<(String, fs::Metadata)>,
+    directories: Vec<(String, fs::Metadata)>,
+}
+
 /// Struct FileTree Implementation
 impl FileTree {
     /// Constructor for the FileTree struct. Initializes a new FileTree with
@@ -38,6 +113,7 @@ impl FileTree {
         Self {
             path,
             files: Vec::new(),
+            sizes: Vec::new(),
             directories: Vec::new(),
         }
     }
@@ -47,74 +123,855 @@ impl FileTree {
     /// This function takes a `ReadDir` iterator (which is a result of the `read_dir` function from `std::fs`)
     /// and returns a tuple of two `Vec<String>`. The first vector contains the paths to files and
     /// the second vector contains the paths to directories.
-    pub fn partition_entries(entries: ReadDir) -> (Vec<String>, Vec<String>) {
-        entries
-            .filter_map(Result::ok) // Filter out errors
-            .map(|entry| entry.path().display().to_string()) // Convert to string
-            .partition(|entry| fs::metadata(entry).unwrap().is_dir()) // Partition into files and directories
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FileTreeError` naming the entry whose metadata could not be read.
+    pub fn partition_entries(
+        entries: ReadDir,
+    ) -> Result<(Vec<String>, Vec<String>), FileTreeError> {
+        let entries = Self::read_entries(entries, true)?;
+        let names = |entries: Vec<(String, fs::Metadata)>| {
+            entries.into_iter().map(|(entry, _)| entry).collect()
+        };
+        Ok((names(entries.files), names(entries.directories)))
+    }
+
+    /// Reads the entries of a directory. Symbolic links are resolved when
+    /// `follow_symlinks` is set and skipped otherwise; dangling links are skipped
+    /// with a warning.
+    fn read_entries(
+        entries: ReadDir,
+        follow_symlinks: bool,
+    ) -> Result<DirectoryEntries, FileTreeError> {
+        let mut files = Vec::new();
+        let mut directories = Vec::new();
+
+        for entry in entries.filter_map(Result::ok) {
+            let entry = entry.path().display().to_string();
+            let metadata = {
+                let _permit = io_limit::acquire();
+                if follow_symlinks {
+                    fs::metadata(&entry)
+                } else {
+                    fs::symlink_metadata(&entry)
+                }
+            };
+
+            match metadata {
+                Ok(metadata) if metadata.is_symlink() => {
+                    log::debug!("Skipping symbolic link {}", entry)
+                }
+                Ok(metadata) if metadata.is_dir() => directories.push((entry, metadata)),
+                Ok(metadata) => files.push((entry, metadata)),
+                Err(error) if follow_symlinks && error.kind() == io::ErrorKind::NotFound => {
+                    log::warn!("Skipping {}, it is a dangling symbolic link", entry)
+                }
+                Err(error) => return Err(FileTreeError::io(entry, error)),
+            }
+        }
+
+        Ok(DirectoryEntries { files, directories })
     }
 
     /// Constructs a new instance of FileTree by reading and processing a directory path.
     ///
+    /// This is a panicking wrapper around `try_from_directory`.
+    ///
     /// # Arguments
     ///
     /// * `path` - The path to the directory to be processed.
-    /// * `season` - The season number corresponding to the recursion depth.
+    /// * `max_depth` - How many levels below `path` to descend (`Some(0)` only reads
+    ///   `path` itself). `None` descends without limit.
+    /// * `exclude` - Glob patterns (e.g. `**/extras/**`) matched against the full path
+    ///   of every entry; matching files and directories are skipped.
+    /// * `include_hidden` - Also index dotfiles and the well-known junk entries of
+    ///   `JUNK_NAMES` (`@eaDir`, `.AppleDouble`, ...), which are skipped otherwise.
     ///
     /// # Returns
     ///
     /// * A new instance of `Self` (FileTree) containing the file tree from the given directory.
-    pub fn from_directory(path: String, season: usize) -> Self {
-        let entries = fs::read_dir(&path).unwrap();
-        let (files, dirs) = Self::partition_entries(entries);
+    pub fn from_directory(
+        path: String,
+        max_depth: Option<usize>,
+        exclude: &[String],
+        include_hidden: bool,
+    ) -> Self {
+        Self::try_from_directory(path, max_depth, exclude, include_hidden).unwrap()
+    }
+
+    /// Constructs a new instance of FileTree by reading and processing a directory path.
+    /// This is a shorthand for the matching `ScanConfig`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory to be processed.
+    /// * `max_depth` - How many levels below `path` to descend (`Some(0)` only reads
+    ///   `path` itself). `None` descends without limit.
+    /// * `exclude` - Glob patterns (e.g. `**/extras/**`) matched against the full path
+    ///   of every entry; matching files and directories are skipped.
+    /// * `include_hidden` - Also index dotfiles and the well-known junk entries of
+    ///   `JUNK_NAMES` (`@eaDir`, `.AppleDouble`, ...), which are skipped otherwise.
+    ///
+    /// Symbolic links are skipped, see `ScanConfig::follow_symlinks` to follow them.
+    /// Entries matching the `.ripignore` file of a directory above them are skipped,
+    /// see `IGNORE_FILE_NAME`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FileTreeError` naming the first directory or entry that could not be
+    /// read, or the first invalid pattern of `exclude`.
+    pub fn try_from_directory(
+        path: String,
+        max_depth: Option<usize>,
+        exclude: &[String],
+        include_hidden: bool,
+    ) -> Result<Self, FileTreeError> {
+        let mut config = ScanConfig::new()
+            .exclude(exclude)
+            .include_hidden(include_hidden);
+        if let Some(max_depth) = max_depth {
+            config = config.max_depth(max_depth);
+        }
+        config.build_from(path)
+    }
+
+    /// Scans each directory of `paths` with the default `ScanConfig` into one tree:
+    /// a synthetic root, with `MULTI_ROOT_PATH` as path and no files, whose
+    /// `directories` are the trees of `paths` in order. This is a panicking wrapper
+    /// around `ScanConfig::build_from_all`.
+    ///
+    /// The roots stay apart; `merge` them instead to treat a library split over
+    /// several drives as one.
+    pub fn from_directories(paths: Vec<String>) -> Self {
+        ScanConfig::new().build_from_all(paths).unwrap()
+    }
+
+    /// Returns the name of the tree, i.e. the last component of its path. Both `/`
+    /// and `\` separate components and trailing separators are ignored; a path
+    /// without any component (e.g. `/`) is returned as is.
+    pub fn name(&self) -> String {
+        let is_separator = |c: char| matches!(c, '/' | '\\');
+        match self
+            .path
+            .trim_end_matches(is_separator)
+            .rsplit(is_separator)
+            .next()
+        {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => self.path.clone(),
+        }
+    }
+
+    /// Combines `other` into this tree, e.g. the parts of a show spread over several
+    /// drives. Files keep their paths, so links point to the drive holding each one.
+    /// Subdirectories with the same name are merged recursively, and the merged tree
+    /// and directories keep the paths of `self`.
+    ///
+    /// Files with the same name in the same merged directory are all kept, with a
+    /// warning: they are linked as separate episodes.
+    pub fn merge(mut self, other: FileTree) -> FileTree {
+        let file_name = |file: &str| Path::new(file).file_name().map(|name| name.to_owned());
+        for file in &other.files {
+            if self
+                .files
+                .iter()
+                .any(|kept| file_name(kept) == file_name(file))
+            {
+                log::warn!("{} is also in {}, keeping both", file, self.path);
+            }
+        }
+
+        // Sizes are only kept when both trees have them
+        if self.sizes.len() == self.files.len() && other.sizes.len() == other.files.len() {
+            self.sizes.extend(other.sizes);
+        } else {
+            self.sizes.clear();
+        }
+        self.files.extend(other.files);
+
+        for directory in other.directories {
+            let name = directory.name();
+            match self.directories.iter().position(|kept| kept.name() == name) {
+                Some(index) => {
+                    let kept = std::mem::replace(
+                        &mut self.directories[index],
+                        FileTree::new(String::new()),
+                    );
+                    self.directories[index] = kept.merge(directory);
+                }
+                None => self.directories.push(directory),
+            }
+        }
+        self
+    }
 
-        // Generate new file names with season and episode numbers
-        let files = files
-            .into_iter()
+    /// Removes the subdirectories holding no file at any depth, e.g. the ones left
+    /// with nothing by a `ScanConfig::extensions` filter. The tree itself is kept,
+    /// even when empty.
+    pub fn prune_empty(&mut self) {
+        for directory in &mut self.directories {
+            directory.prune_empty();
+        }
+        self.directories
+            .retain(|directory| !directory.files.is_empty() || !directory.directories.is_empty());
+    }
+
+    /// Renders the tree with box-drawing connectors, one entry per line. Files are
+    /// listed before directories and both are in natural order.
+    ///
+    /// # Arguments
+    ///
+    /// * `full_paths` - Print the full path of every entry instead of its name.
+    ///
+    /// # Returns
+    ///
+    /// * The rendered tree, terminated by a newline.
+    pub fn to_file_tree(&self, full_paths: bool) -> String {
+        let mut output = String::new();
+        // Writing to a String cannot fail
+        let _ = self.render_tree(full_paths, false, &mut output);
+        output
+    }
+
+    /// Like `to_file_tree`, but every file is followed by its size in human-readable
+    /// form (e.g. `(1.4 GiB)`) when it is known.
+    pub fn to_file_tree_with_sizes(&self, full_paths: bool) -> String {
+        let mut output = String::new();
+        let _ = self.render_tree(full_paths, true, &mut output);
+        output
+    }
+
+    /// Renders the tree as a nested Markdown list, e.g. for a wiki page: one `- `
+    /// bullet per entry, indented by two spaces per level, with directory names in
+    /// bold. Entries are ordered like `to_file_tree` and Markdown syntax in names
+    /// is escaped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use file_tree::FileTree;
+    ///
+    /// let tree = FileTree::from_string_vector(vec![
+    ///     "/media/Show/Season 1/e1.mkv".to_string(),
+    ///     "/media/Show/cover_art.jpg".to_string(),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(
+    ///     tree.to_markdown(),
+    ///     "- **Show**\n  - cover\\_art.jpg\n  - **Season 1**\n    - e1.mkv\n"
+    /// );
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        let mut output = String::new();
+        self.render_markdown("", &mut output);
+        output
+    }
+
+    fn render_markdown(&self, indent: &str, output: &mut String) {
+        output.push_str(&format!(
+            "{}- **{}**\n",
+            indent,
+            escape_markdown(&self.name())
+        ));
+        let indent = format!("{}  ", indent);
+        let mut files: Vec<&String> = self.files.iter().collect();
+        files.sort_by(|a, b| natural_cmp(a, b));
+        for file in files {
+            let name = Path::new(file).file_name().unwrap().to_string_lossy();
+            output.push_str(&format!("{}- {}\n", indent, escape_markdown(&name)));
+        }
+        let mut directories: Vec<&FileTree> = self.directories.iter().collect();
+        directories.sort_by(|a, b| natural_cmp(&a.path, &b.path));
+        for directory in directories {
+            directory.render_markdown(&indent, output);
+        }
+    }
+
+    /// Renders the tree as a Graphviz DOT digraph, e.g. for `dot -Tpng`: one node per
+    /// directory (shaped as a folder) and file (as a note), labeled with its name,
+    /// and an edge from each directory to every entry it contains. Nodes are
+    /// identified by their quoted full path, so IDs are stable across renders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use file_tree::FileTree;
+    ///
+    /// let tree = FileTree::from_string_vector(vec!["/media/Show/e1.mkv".to_string()]).unwrap();
+    /// assert_eq!(
+    ///     tree.to_dot(),
+    ///     "digraph tree {\n    \
+    ///         \"/media/Show\" [label=\"Show\", shape=folder];\n    \
+    ///         \"/media/Show/e1.mkv\" [label=\"e1.mkv\", shape=note];\n    \
+    ///         \"/media/Show\" -> \"/media/Show/e1.mkv\";\n\
+    ///     }\n"
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut output = String::from("digraph tree {\n");
+        output.push_str(&format!(
+            "    {} [label={}, shape=folder];\n",
+            dot_quote(&self.path),
+            dot_quote(&self.name())
+        ));
+        self.render_dot(&mut output);
+        output.push_str("}\n");
+        output
+    }
+
+    // The nodes of the entries of this directory and the edges leading to them
+    fn render_dot(&self, output: &mut String) {
+        let id = dot_quote(&self.path);
+        let mut files: Vec<&String> = self.files.iter().collect();
+        files.sort_by(|a, b| natural_cmp(a, b));
+        for file in files {
+            let name = Path::new(file).file_name().unwrap().to_string_lossy();
+            output.push_str(&format!(
+                "    {} [label={}, shape=note];\n",
+                dot_quote(file),
+                dot_quote(&name)
+            ));
+            output.push_str(&format!("    {} -> {};\n", id, dot_quote(file)));
+        }
+        let mut directories: Vec<&FileTree> = self.directories.iter().collect();
+        directories.sort_by(|a, b| natural_cmp(&a.path, &b.path));
+        for directory in directories {
+            output.push_str(&format!(
+                "    {} [label={}, shape=folder];\n",
+                dot_quote(&directory.path),
+                dot_quote(&directory.name())
+            ));
+            output.push_str(&format!("    {} -> {};\n", id, dot_quote(&directory.path)));
+            directory.render_dot(output);
+        }
+    }
+
+    fn render_tree(
+        &self,
+        full_paths: bool,
+        show_sizes: bool,
+        output: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let root = if full_paths {
+            self.path.clone()
+        } else {
+            self.name()
+        };
+        writeln!(output, "{}", root)?;
+        self.format_tree_entries("", full_paths, show_sizes, output)
+    }
+
+    fn format_tree_entries(
+        &self,
+        prefix: &str,
+        full_paths: bool,
+        show_sizes: bool,
+        output: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let mut files: Vec<(&str, Option<u64>)> = self
+            .files
+            .iter()
             .enumerate()
-            .map(|(i, file)| {
-                let file_stem = Path::new(&file)
-                    .file_stem()
-                    .unwrap()
-                    .to_str()
+            .map(|(i, file)| (file.as_str(), self.sizes.get(i).copied()))
+            .collect();
+        files.sort_by(|a, b| natural_cmp(a.0, b.0));
+        let mut directories: Vec<&FileTree> = self.directories.iter().collect();
+        directories.sort_by(|a, b| natural_cmp(&a.path, &b.path));
+
+        let count = files.len() + directories.len();
+        let connector = |index: usize| {
+            if index + 1 == count {
+                "└── "
+            } else {
+                "├── "
+            }
+        };
+
+        for (index, (file, size)) in files.into_iter().enumerate() {
+            let mut label = if full_paths {
+                file.to_string()
+            } else {
+                Path::new(file)
+                    .file_name()
                     .unwrap()
-                    .to_owned();
-                format!("S{:02}E{:02} - {}", season, i + 1, file_stem)
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            if let (true, Some(size)) = (show_sizes, size) {
+                label.push_str(&format!(" ({})", human_size(size)));
+            }
+            writeln!(output, "{}{}{}", prefix, connector(index), label)?;
+        }
+
+        let offset = count - directories.len();
+        for (index, directory) in directories.into_iter().enumerate() {
+            let index = offset + index;
+            let label = if full_paths {
+                directory.path.clone()
+            } else {
+                directory.name()
+            };
+            writeln!(output, "{}{}{}", prefix, connector(index), label)?;
+
+            let continuation = if index + 1 == count { "    " } else { "│   " };
+            directory.format_tree_entries(
+                &format!("{}{}", prefix, continuation),
+                full_paths,
+                show_sizes,
+                output,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Whether both trees hold the same files and directories at the same paths,
+    /// whatever their order. Traversal order varies across filesystems, so this is
+    /// the comparison to use between two scans. File sizes are not compared.
+    pub fn structurally_eq(&self, other: &FileTree) -> bool {
+        if self.path != other.path
+            || self.files.len() != other.files.len()
+            || self.directories.len() != other.directories.len()
+        {
+            return false;
+        }
+
+        let mut files: Vec<&String> = self.files.iter().collect();
+        let mut other_files: Vec<&String> = other.files.iter().collect();
+        files.sort();
+        other_files.sort();
+        if files != other_files {
+            return false;
+        }
+
+        let mut directories: Vec<&FileTree> = self.directories.iter().collect();
+        let mut other_directories: Vec<&FileTree> = other.directories.iter().collect();
+        directories.sort_by(|a, b| a.path.cmp(&b.path));
+        other_directories.sort_by(|a, b| a.path.cmp(&b.path));
+        directories
+            .iter()
+            .zip(other_directories)
+            .all(|(directory, other)| directory.structurally_eq(other))
+    }
+
+    /// Lazily walks the file paths of the tree, depth first, without cloning them.
+    /// `for file in &tree` does the same.
+    pub fn iter_files(&self) -> Files<'_> {
+        Files::new(self)
+    }
+
+    /// Flattens the tree into a list of file paths, depth first, as stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - A string prepended to every path (e.g. a mount point).
+    pub fn to_file_list(&self, prefix: &str) -> Vec<String> {
+        self.iter_files()
+            .map(|file| format!("{}{}", prefix, file))
+            .collect()
+    }
+
+    /// Returns the paths of the files of the whole tree matching `predicate`,
+    /// depth first.
+    pub fn find<F: Fn(&str) -> bool>(&self, predicate: F) -> Vec<String> {
+        self.iter_files()
+            .filter(|file| predicate(file))
+            .map(String::from)
+            .collect()
+    }
+
+    /// Returns the paths of the files of the whole tree ending with `extension`
+    /// (e.g. `.mkv`), matched case-insensitively, depth first.
+    pub fn find_by_extension(&self, extension: &str) -> Vec<String> {
+        let extension = extension.to_lowercase();
+        self.find(|file| file.to_lowercase().ends_with(&extension))
+    }
+
+    /// Serializes the tree to a compact JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Deserializes a tree saved with `to_json`.
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// Serializes the tree to a YAML document.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+
+    /// Returns the media files of this directory a run with `options` links: the
+    /// ones whose extension matches one of `options.extensions` (case-insensitive),
+    /// falling back to `DEFAULT_MEDIA_EXTENSIONS` when empty, that are at least
+    /// `options.min_size` bytes large and were modified after
+    /// `options.modified_since` when set. The files are returned in `order`,
+    /// natural by default so episodes are numbered as a human expects.
+    fn generate_file_list(&self, options: &LinkOptions, order: SortOrder) -> Vec<&str> {
+        let is_media = extension_matcher(&options.extensions);
+        let mut files: Vec<&str> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| is_media(file))
+            .filter(|(index, file)| match options.min_size {
+                // Only stat the file when there is a minimum to check
+                Some(min_size) if self.file_size(*index).is_some_and(|size| size < min_size) => {
+                    log::debug!("Skipping {}, it is smaller than {} bytes", file, min_size);
+                    false
+                }
+                _ => true,
             })
+            .filter(|(_, file)| match options.modified_since {
+                // Same for the modification time and the cutoff
+                Some(since) if modified(file).is_some_and(|modified| modified < since) => {
+                    log::debug!("Skipping {}, it was not modified since the cutoff", file);
+                    false
+                }
+                _ => true,
+            })
+            .map(|(_, file)| file.as_str())
             .collect();
+        order.sort_by_key(&mut files, |file| file);
+        files
+    }
 
-        Self {
-            path,
-            files,
-            directories: dirs
-                .into_iter()
-                .map(|dir| Self::from_directory(dir, season + 1))
-                .collect(),
+    /// Size of the file at `index` in `files`, read from disk when the tree does
+    /// not hold it (e.g. a tree parsed from a listing). `None` when it cannot be read.
+    fn file_size(&self, index: usize) -> Option<u64> {
+        if let Some(size) = self.sizes.get(index) {
+            return Some(*size);
         }
+        let _permit = io_limit::acquire();
+        fs::metadata(&self.files[index])
+            .map(|metadata| metadata.len())
+            .ok()
     }
 
-    pub fn create_grouped_symlinks(self, destination: String) {
-        let new_path = Path::new(&destination);
-        if !new_path.exists() {
-            fs::create_dir_all(&new_path).unwrap();
+    /// Renders an extended M3U playlist of the media files of the whole tree, in
+    /// natural order.
+    ///
+    /// # Arguments
+    ///
+    /// * `extensions` - The extensions of the files to include, as for
+    ///   `create_grouped_symlinks`.
+    /// * `relative_to` - When set, entries are written relative to this directory
+    ///   (usually the one holding the playlist) instead of as absolute paths.
+    pub fn to_m3u(&self, extensions: &[String], relative_to: Option<&Path>) -> String {
+        let is_media = extension_matcher(extensions);
+        let mut files: Vec<&str> = self.iter_files().filter(|file| is_media(file)).collect();
+        files.sort_by(|a, b| natural_cmp(a, b));
+
+        let mut playlist = String::from("#EXTM3U\n");
+        for file in files {
+            let entry = relative_to
+                .and_then(|directory| link::relative_path(directory, Path::new(file)))
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| file.to_string());
+            playlist.push_str(&entry);
+            playlist.push('\n');
         }
+        playlist
+    }
+}
+
+/// Renders the tree like `to_file_tree`, with entry names. The alternate form
+/// (`{:#}`) prints full paths instead.
+impl fmt::Display for FileTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render_tree(f.alternate(), false, f)
+    }
+}
 
-        for (i, file) in self.files.iter().enumerate() {
-            let new_file_path = new_path.join(file);
-            let old_file_path =
-                Path::new(&self.path).join(format!("S{:02}E{:02} - {}", 1, i + 1, file));
+/// Returns a predicate telling whether a path has one of `extensions`
+/// (case-insensitive), falling back to `DEFAULT_MEDIA_EXTENSIONS` when empty.
+fn extension_matcher(extensions: &[String]) -> impl Fn(&str) -> bool {
+    let extensions: Vec<String> = if extensions.is_empty() {
+        default_extensions()
+    } else {
+        extensions.iter().map(|ext| ext.to_lowercase()).collect()
+    };
 
-            if let Err(error) = std::os::unix::fs::symlink(&old_file_path, &new_file_path) {
-                println!(
-                    "Error creating symbolic link: {} -> {}",
-                    new_file_path.display(),
-                    error
-                );
-            }
+    move |file| {
+        let file = file.to_lowercase();
+        extensions.iter().any(|ext| file.ends_with(ext.as_str()))
+    }
+}
+
+/// Formats a byte count with binary units, e.g. `1.4 GiB`.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Cleans up `path` lexically: repeated separators, `.` components and trailing
+/// separators are dropped, e.g. `./media//Show/./` becomes `media/Show`. `..` is
+/// kept, since resolving it without the filesystem is wrong across symbolic links.
+/// Trees, scans and link destinations go through it, so their paths are clean.
+pub fn normalize_path(path: impl AsRef<Path>) -> PathBuf {
+    let normalized: PathBuf = path
+        .as_ref()
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect();
+    if normalized.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        normalized
+    }
+}
+
+/// Parses a human-readable size such as `50MB`, `1.5 GiB` or `4096`. `KB`, `MB`,
+/// `GB` and `TB` are powers of 1000, while `KiB`, `MiB`, ... and the bare `K`, `M`,
+/// `G` and `T` are powers of 1024. Units are case-insensitive.
+///
+/// # Errors
+///
+/// Returns `FileTreeError::InvalidSize` when `text` is not a non-negative number
+/// followed by an optional known unit.
+pub fn parse_size(text: &str) -> Result<u64, FileTreeError> {
+    let invalid = || FileTreeError::InvalidSize {
+        text: text.to_string(),
+    };
+    let trimmed = text.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_u64.pow(2),
+        "gb" => 1000_u64.pow(3),
+        "tb" => 1000_u64.pow(4),
+        "k" | "kib" => 1024,
+        "m" | "mib" => 1024_u64.pow(2),
+        "g" | "gib" => 1024_u64.pow(3),
+        "t" | "tib" => 1024_u64.pow(4),
+        _ => return Err(invalid()),
+    };
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Parses the cutoff of `LinkOptions::modified_since`: an ISO-8601 date, or date
+/// and time in UTC (`2024-05-01`, `2024-05-01T18:30` or `2024-05-01T18:30:00Z`),
+/// or a time relative to now such as `90m`, `12h`, `7d` or `2w` (`s`, `m`, `h`,
+/// `d` and `w` are supported).
+///
+/// # Errors
+///
+/// Returns `FileTreeError::InvalidDate` when `text` is neither, or names a date
+/// that does not exist (e.g. `2023-02-29`) or is before 1970.
+pub fn parse_since(text: &str) -> Result<SystemTime, FileTreeError> {
+    let invalid = || FileTreeError::InvalidDate {
+        text: text.to_string(),
+    };
+    let trimmed = text.trim();
+    if let Some(seconds) = relative_seconds(trimmed) {
+        return SystemTime::now()
+            .checked_sub(Duration::from_secs(seconds))
+            .ok_or_else(invalid);
+    }
+    let seconds = epoch_seconds(trimmed).ok_or_else(invalid)?;
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(seconds))
+        .ok_or_else(invalid)
+}
+
+// Seconds in a relative time such as `7d`
+fn relative_seconds(text: &str) -> Option<u64> {
+    let (number, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit())?);
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+// Seconds since the Unix epoch of an ISO-8601 date, or date and time in UTC
+fn epoch_seconds(text: &str) -> Option<u64> {
+    let text = text.strip_suffix(['Z', 'z']).unwrap_or(text);
+    let (date, time) = match text.split_once(['T', 't', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    let numbers = |text: &str, separator: char| -> Option<Vec<u32>> {
+        text.split(separator)
+            .map(|part| match part.bytes().all(|c| c.is_ascii_digit()) {
+                true => part.parse().ok(),
+                false => None,
+            })
+            .collect()
+    };
+
+    let (year, month, day) = match numbers(date, '-')?[..] {
+        [year, month, day] => (i64::from(year), month, day),
+        _ => return None,
+    };
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+    let (hour, minute, second) = match time.map(|time| numbers(time, ':')) {
+        None => (0, 0, 0),
+        Some(Some(parts)) => match parts[..] {
+            [hour, minute] => (hour, minute, 0),
+            [hour, minute, second] => (hour, minute, second),
+            _ => return None,
+        },
+        Some(None) => return None,
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year, month.into(), day.into())).ok()?;
+    Some(days * 86_400 + u64::from(hour * 3600 + minute * 60 + second))
+}
+
+// Days from 1970-01-01 to the given date, negative before it
+// (http://howardhinnant.github.io/date_algorithms.html#days_from_civil)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Last modification time of the file at `path`, `None` when it cannot be read.
+fn modified(path: &str) -> Option<SystemTime> {
+    let _permit = io_limit::acquire();
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Whether the last component of `path` is a dotfile or one of `JUNK_NAMES`.
+fn is_hidden_or_junk(path: &str) -> bool {
+    match Path::new(path).file_name() {
+        Some(name) => {
+            let name = name.to_string_lossy();
+            name.starts_with('.') || JUNK_NAMES.contains(&name.as_ref())
         }
+        None => false,
+    }
+}
 
-        for directory in self.directories {
-            directory.create_grouped_symlinks(destination.clone());
+/// Compiles glob patterns into a single matcher.
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, FileTreeError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|source| FileTreeError::InvalidPattern {
+            pattern: pattern.clone(),
+            source,
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|source| FileTreeError::InvalidPattern {
+            pattern: patterns.join(", "),
+            source,
+        })
+}
+
+// Escapes the characters of `text` Markdown would read as formatting or links
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
         }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// `text` as a quoted DOT ID, with quotes and backslashes escaped
+fn dot_quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Returns `DEFAULT_MEDIA_EXTENSIONS` as owned strings, ready to be extended and
+/// passed wherever a list of extensions is expected.
+///
+/// # Example
+///
+/// ```
+/// use file_tree::default_extensions;
+///
+/// let mut extensions = default_extensions();
+/// extensions.push(".mkv".to_string());
+/// assert_eq!(extensions, [".mp4", ".mkv"]);
+/// ```
+pub fn default_extensions() -> Vec<String> {
+    DEFAULT_MEDIA_EXTENSIONS
+        .iter()
+        .map(|ext| ext.to_string())
+        .collect()
+}
+
+/// Returns the extension of `path` including the leading dot (e.g. `.mkv`),
+/// or an empty string when the file has none.
+pub fn extension_of(path: &str) -> &str {
+    match Path::new(path).extension() {
+        Some(extension) => &path[path.len() - extension.len() - 1..],
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_file_list_without_filters_does_not_stat() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<String> = ["a.mp4", "b.mp4"]
+            .iter()
+            .map(|name| {
+                let file = dir.path().join(name);
+                fs::write(&file, "video").unwrap();
+                file.display().to_string()
+            })
+            .collect();
+        // A tree parsed from a listing holds no sizes, filters would stat its files
+        let tree = FileTree::from_string_vector(files).unwrap();
+
+        let before = io_limit::acquired();
+        let files = tree.generate_file_list(&LinkOptions::default(), SortOrder::Natural);
+        assert_eq!(files.len(), 2);
+        assert_eq!(io_limit::acquired(), before);
     }
 }
@@ -0,0 +1,1756 @@
+use crate::rename::{RenamePattern, Renamer};
+use crate::template::{GroupTemplate, PLEX_GROUP_TEMPLATE};
+use crate::{
+    dedupe, episode, extension_of, human_size, io_limit, nfo, normalize_path, FileTree,
+    FileTreeError, GroupingType, SortOrder, MULTI_ROOT_PATH,
+};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+// Extensions of the subtitle files linked alongside their video
+const SUBTITLE_EXTENSIONS: [&str; 6] = [".srt", ".ass", ".ssa", ".sub", ".idx", ".vtt"];
+
+// Longest file name, in bytes, accepted by common filesystems (ext4, NTFS, APFS)
+const MAX_NAME_BYTES: usize = 255;
+
+// Characters that are not allowed in a file name on at least one common filesystem
+const ILLEGAL_NAME_CHARACTERS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Kind of link created for each media file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkMode {
+    /// Symbolic links, which may cross filesystems
+    #[default]
+    Symlink,
+    /// Hard links, which survive moving the source within its filesystem but
+    /// cannot cross devices
+    Hardlink,
+    /// Copies of the files, for filesystems without links such as FAT32 drives or
+    /// some network shares. Every copy takes the space of its source.
+    Copy,
+}
+
+/// How the media files are bucketed into seasons
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Every file is a season of its own
+    Filename,
+    /// Every directory holding media files is a season, in depth-first order
+    #[default]
+    ParentDir,
+    /// Files are bucketed by the capture named `group` of the pattern applied to
+    /// their name (e.g. `(?P<group>.+)\.S\d+E\d+` buckets `Show.Name.S01E02.mkv`
+    /// under `Show.Name`), the buckets being ordered by their key. Keys are
+    /// compared case-insensitively, and accent-insensitively with the `unicode`
+    /// feature; a bucket is named after the key of its first file. Files that do
+    /// not match end up in a last "Ungrouped" bucket.
+    Regex(String),
+}
+
+/// Order of the seasons, which decides their numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupSort {
+    /// The order of `GroupBy`, i.e. by directory or regex key in `LinkOptions::sort_order`
+    #[default]
+    Name,
+    /// By number of media files, fewest first
+    Count,
+    /// By total size of the media files, smallest first
+    Size,
+    /// By modification time of the newest media file, oldest first, so new content
+    /// gets the highest season
+    Mtime,
+}
+
+// Name of the capture keying `GroupBy::Regex` buckets
+const GROUP_CAPTURE: &str = "group";
+
+impl GroupBy {
+    /// Checks that a `Regex` pattern compiles and has a capture named `group`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FileTreeError::InvalidRegex` or `FileTreeError::MissingGroupCapture`.
+    pub fn validate(&self) -> Result<(), FileTreeError> {
+        match self {
+            GroupBy::Regex(pattern) => compile_group_regex(pattern).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Normalizes a `GroupBy::Regex` key so that `Naruto` and `NARUTO` (and, with the
+/// `unicode` feature, `Pokémon` and `Pokemon`) end up in the same bucket.
+fn normalize_group_key(key: &str) -> String {
+    #[cfg(feature = "unicode")]
+    let key: String = {
+        use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+        key.nfd().filter(|c| !is_combining_mark(*c)).collect()
+    };
+    key.to_lowercase()
+}
+
+fn compile_group_regex(pattern: &str) -> Result<Regex, FileTreeError> {
+    let regex = Regex::new(pattern).map_err(|source| FileTreeError::InvalidRegex {
+        pattern: pattern.to_string(),
+        source,
+    })?;
+    if !regex
+        .capture_names()
+        .flatten()
+        .any(|name| name == GROUP_CAPTURE)
+    {
+        return Err(FileTreeError::MissingGroupCapture {
+            pattern: pattern.to_string(),
+        });
+    }
+    Ok(regex)
+}
+
+/// Options of a `create_grouped_symlinks` run
+#[derive(Clone, Debug, Default)]
+pub struct LinkOptions {
+    /// The file extensions to link (e.g. `.mkv`), matched case-insensitively.
+    /// When empty, `DEFAULT_MEDIA_EXTENSIONS` is used.
+    pub extensions: Vec<String>,
+    /// Extensions of the files linked along with each media file sharing their stem,
+    /// e.g. `.jpg` thumbnails or `.nfo` files (`Episode.jpg` for `Episode.mkv`), in
+    /// addition to subtitles. They are renamed after the link of the media file.
+    pub companion_extensions: Vec<String>,
+    /// The naming scheme of the created directories and links.
+    pub grouping: GroupingType,
+    /// How the files are bucketed into seasons.
+    pub group_by: GroupBy,
+    /// Order of the seasons. Seasons tied on count, size or time keep their
+    /// `GroupBy` order.
+    pub group_sort: GroupSort,
+    /// Name of the directory holding the links of each season, with the `{index}`
+    /// (`{index:02}` zero-pads it), `{name}` and `{type}` placeholders, e.g.
+    /// `{type} {index} - {name}`. The name is the one of the season's directory,
+    /// regex key or file stem, and the type is `Season` in Plex mode and `Group`
+    /// otherwise. Plex mode defaults to `Season {index:02}`, while the links are
+    /// created directly in the destination by default otherwise.
+    pub group_template: Option<String>,
+    /// Order of the seasons and of the episodes within them, which decides their
+    /// numbers. Regex buckets are ordered by their key, other seasons follow the
+    /// order of their directories.
+    pub sort_order: SortOrder,
+    /// Whether symbolic links, hard links or copies are created.
+    pub link_mode: LinkMode,
+    /// Store the path of each source relative to its symbolic link instead of the
+    /// absolute path, so the links survive moving or remounting the library. Falls
+    /// back to an absolute link (with a warning) when both are on different roots.
+    /// Hard links have no stored path and ignore it.
+    pub relative: bool,
+    /// In Plex mode, also write an `<episodedetails>` `.nfo` file next to each link
+    /// with its season, episode and the original file name as title.
+    pub write_nfo: bool,
+    /// Log the directories and links that would be created (at the info level)
+    /// instead of touching the filesystem.
+    pub dry_run: bool,
+    /// Only link the first (in natural order) of the media files holding the same
+    /// content, see `FileTree::find_duplicates`.
+    pub dedupe: bool,
+    /// Markers of the preferred qualities, best first (e.g. `["1080p", "720p"]` or
+    /// `[".mkv"]`). When a directory holds several copies of an episode, e.g.
+    /// `Episode.1080p.mkv` and `Episode.720p.mp4`, only the one containing the
+    /// earliest marker is linked. Every file is linked when empty.
+    pub quality_priority: Vec<String>,
+    /// Skip the media files smaller than this many bytes, e.g. samples.
+    pub min_size: Option<u64>,
+    /// Skip the media files last modified before this time, e.g. the ones a previous
+    /// nightly run already linked. See `parse_since` to read it from text.
+    pub modified_since: Option<SystemTime>,
+    /// Number of the first season, 1 when unset, e.g. to add a new run to the seasons
+    /// already in a library. Numbers found in the file names are kept as they are.
+    /// It cannot be 0 along with `specials`, which are numbered in season 0.
+    pub season_start: Option<u32>,
+    /// Number of the first episode of the first season, 1 when unset. The following
+    /// seasons start at episode 1.
+    pub episode_start: Option<u32>,
+    /// Keywords marking specials (e.g. `DEFAULT_SPECIALS_KEYWORDS`), matched as whole
+    /// words in the file name or the name of its directory, e.g. `Show - OVA.mkv` or
+    /// `Specials/Pilot.mkv`. Specials are taken out of their group and numbered in
+    /// season 0 (`Season 00` in Plex mode). Nothing is a special when empty. Flattened
+    /// and mirrored runs ignore it.
+    pub specials: Vec<String>,
+    /// Link every media file directly in the destination, named `0001 - <file name>`,
+    /// `0002 - <file name>`, ... after the order of their paths. Grouping, naming
+    /// templates and `.nfo` files do not apply.
+    pub flatten: bool,
+    /// Recreate the directories of the tree in the destination and link every media
+    /// file at the same relative path, under its own name. Grouping, naming templates
+    /// and `.nfo` files do not apply, and `flatten` is ignored.
+    pub mirror: bool,
+    /// After creating them, check that the links resolve to a readable file (and
+    /// hard links to their source), and that copies hash like their source.
+    pub verify: bool,
+    /// Rewrites of the link names, applied in order to each media file's link name
+    /// (extension excluded) before it is sanitized, e.g. to strip release tags.
+    /// Subtitles and companion files follow their video's name. Mirrored runs and
+    /// directory links keep their names.
+    pub rename_patterns: Vec<RenamePattern>,
+    /// Lowercase the extension of the links (`Pilot.MKV` is linked as `Pilot.mkv`),
+    /// subtitles' and companion files' included. The sources keep their names, and
+    /// mirrored runs the names of the sources.
+    pub normalize_extension_case: bool,
+    /// Link each directory holding media files as a whole, with one symbolic link
+    /// named like its season directory (e.g. `Season 01`, or the directory's own name
+    /// without a group template), instead of linking its files one by one. Much
+    /// faster for large libraries already laid out for Plex, but everything in the
+    /// directories is exposed, subdirectories included. Requires symbolic links and
+    /// grouping by parent directory; specials and `.nfo` files do not apply.
+    pub link_directories: bool,
+    /// Fail the run, before anything is created, when a file of the tree no longer
+    /// exists instead of leaving it out.
+    pub strict: bool,
+    /// Delete a regular file found where a link goes (e.g. left by a copy run) and
+    /// link in its place. Without it such files are left alone and reported as
+    /// conflicts. Directories are never deleted.
+    pub force: bool,
+}
+
+impl LinkOptions {
+    /// Checks the grouping pattern (see `GroupBy::validate`), the group template, the
+    /// rename patterns and that `link_directories` is only combined with options it supports.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `FileTreeError` of the first invalid setting.
+    pub fn validate(&self) -> Result<(), FileTreeError> {
+        self.group_by.validate()?;
+        self.parse_group_template()?;
+        for pattern in &self.rename_patterns {
+            pattern.compile()?;
+        }
+        if self.season_start == Some(0) && !self.specials.is_empty() {
+            return Err(FileTreeError::InvalidOptions {
+                reason: "seasons cannot start at 0 along with specials, they are season 0"
+                    .to_string(),
+            });
+        }
+        if self.link_directories {
+            let reason = if self.link_mode != LinkMode::Symlink {
+                Some("directories can only be linked with symbolic links")
+            } else if self.group_by != GroupBy::ParentDir {
+                Some("directories can only be linked when grouping by parent directory")
+            } else if self.flatten || self.mirror {
+                Some("directories cannot be linked in a flattened or mirrored run")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(FileTreeError::InvalidOptions {
+                    reason: reason.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_group_template(&self) -> Result<Option<GroupTemplate>, FileTreeError> {
+        match (&self.group_template, self.grouping) {
+            (Some(template), _) => GroupTemplate::parse(template).map(Some),
+            (None, GroupingType::Plex) => GroupTemplate::parse(PLEX_GROUP_TEMPLATE).map(Some),
+            (None, GroupingType::Original) => Ok(None),
+        }
+    }
+}
+
+/// Outcome of a `create_grouped_symlinks` run (or, for a dry run, of the planned one)
+#[derive(Debug, Default)]
+pub struct SymlinkReport {
+    /// Directories created
+    pub directories: usize,
+    /// Links created
+    pub created: Vec<PathBuf>,
+    /// Links that already existed and pointed to the right file
+    pub skipped: Vec<PathBuf>,
+    /// Links that existed but pointed elsewhere and were recreated
+    pub replaced: Vec<PathBuf>,
+    /// Files of the tree that no longer exist (or are broken links), left unlinked
+    pub missing: Vec<PathBuf>,
+    /// Paths where a link goes but a regular file (without `LinkOptions::force`) or
+    /// a directory already is, left alone
+    pub conflicts: Vec<PathBuf>,
+    /// With `LinkOptions::verify`, the links and copies created by the run that
+    /// passed verification
+    pub verified: Vec<PathBuf>,
+    /// The directories, links and `.nfo` files that could not be created, each as a
+    /// `FileTreeError::Io` naming the path, and the ones that failed verification as
+    /// `FileTreeError::VerificationFailed`
+    pub failures: Vec<FileTreeError>,
+    /// In a dry run, the operations that would be carried out, in order
+    pub planned: Vec<PlannedAction>,
+    /// The episode each media file was linked as, in season order. Files whose link
+    /// could not be created are left out.
+    pub mappings: Vec<LinkMapping>,
+}
+
+impl SymlinkReport {
+    /// Whether every planned directory, link and `.nfo` file was created and no link
+    /// was kept out by an existing entry.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty() && self.conflicts.is_empty()
+    }
+
+    /// Renders `mappings` as CSV with a header row, with the columns `group`,
+    /// `index`, `source_path`, `link_path`, `season` and `episode`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("group,index,source_path,link_path,season,episode\n");
+        for mapping in &self.mappings {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                csv_field(&mapping.group),
+                mapping.index,
+                csv_field(&mapping.source.display().to_string()),
+                csv_field(&mapping.link.display().to_string()),
+                mapping.season,
+                mapping.episode
+            ));
+        }
+        csv
+    }
+}
+
+/// Where a media file was linked by a grouped `create_grouped_symlinks` run.
+/// Flattened and mirrored runs, and runs linking directories, number no episodes
+/// and record none.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LinkMapping {
+    /// Name of the group (season directory, regex key or file stem)
+    pub group: String,
+    /// Position of the group among the seasons, from 1, or 0 for the specials
+    pub index: u32,
+    /// The linked media file
+    pub source: PathBuf,
+    /// The link created for it
+    pub link: PathBuf,
+    /// Season number in the link name
+    pub season: u32,
+    /// Episode number in the link name
+    pub episode: u32,
+}
+
+// Quotes a CSV field holding a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A filesystem operation of a `create_grouped_symlinks` run. Serializes to e.g.
+/// `{"action": "symlink", "source": "/media/a.mkv", "target": "/links/S01E01 - a.mkv"}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlannedAction {
+    /// Create the directory `target` and its missing parents
+    CreateDir { target: PathBuf },
+    /// Create a symbolic link at `target` storing `source`, which is relative to
+    /// the link's directory in relative mode. `replace` is set when another link
+    /// (or, with `LinkOptions::force`, a file) is in the way.
+    Symlink {
+        source: PathBuf,
+        target: PathBuf,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        replace: bool,
+    },
+    /// Create a hard link at `target` to `source`, replacing the symbolic link (or,
+    /// with `LinkOptions::force`, the file) in the way when `replace` is set
+    Hardlink {
+        source: PathBuf,
+        target: PathBuf,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        replace: bool,
+    },
+    /// Copy `source` to `target`, overwriting the file in the way when `replace`
+    /// is set
+    Copy {
+        source: PathBuf,
+        target: PathBuf,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        replace: bool,
+    },
+    /// Write the episode `.nfo` file `target`, titled with the original file name
+    WriteNfo {
+        target: PathBuf,
+        title: String,
+        season: u32,
+        episode: u32,
+    },
+}
+
+impl PlannedAction {
+    /// The path the action creates.
+    pub fn target(&self) -> &Path {
+        match self {
+            PlannedAction::CreateDir { target }
+            | PlannedAction::Symlink { target, .. }
+            | PlannedAction::Hardlink { target, .. }
+            | PlannedAction::Copy { target, .. }
+            | PlannedAction::WriteNfo { target, .. } => target,
+        }
+    }
+}
+
+impl fmt::Display for PlannedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannedAction::CreateDir { target } => write!(f, "create_dir({})", target.display()),
+            PlannedAction::Symlink { source, target, .. } => {
+                write!(f, "symlink({} -> {})", source.display(), target.display())
+            }
+            PlannedAction::Hardlink { source, target, .. } => {
+                write!(f, "hard_link({} -> {})", source.display(), target.display())
+            }
+            PlannedAction::Copy { source, target, .. } => {
+                write!(f, "copy({} -> {})", source.display(), target.display())
+            }
+            PlannedAction::WriteNfo { target, .. } => write!(f, "write_nfo({})", target.display()),
+        }
+    }
+}
+
+/// Outcome of `apply`
+#[derive(Debug, Default)]
+pub struct ApplyReport {
+    /// Directories created
+    pub directories: usize,
+    /// Links created
+    pub created: Vec<PathBuf>,
+    /// Links created in place of another one
+    pub replaced: Vec<PathBuf>,
+    /// The errors of the actions that failed
+    pub errors: Vec<FileTreeError>,
+}
+
+impl ApplyReport {
+    fn record(&mut self, action: &PlannedAction) {
+        match action {
+            PlannedAction::CreateDir { .. } => self.directories += 1,
+            PlannedAction::Symlink {
+                target,
+                replace: true,
+                ..
+            }
+            | PlannedAction::Hardlink {
+                target,
+                replace: true,
+                ..
+            }
+            | PlannedAction::Copy {
+                target,
+                replace: true,
+                ..
+            } => self.replaced.push(target.clone()),
+            PlannedAction::Symlink { target, .. }
+            | PlannedAction::Hardlink { target, .. }
+            | PlannedAction::Copy { target, .. } => self.created.push(target.clone()),
+            PlannedAction::WriteNfo { .. } => {}
+        }
+    }
+}
+
+/// Progress of `apply_with_progress`, reported after each action
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<'a> {
+    /// Number of actions to carry out
+    pub total: usize,
+    /// Number of actions carried out so far, failed ones included
+    pub completed: usize,
+    /// The path created by the last action
+    pub current_path: &'a Path,
+}
+
+/// Carries out `actions` in order, e.g. a plan made by `FileTree::plan` and
+/// reviewed beforehand. A failed action is logged and reported, and the following
+/// ones are still attempted.
+pub fn apply(actions: &[PlannedAction]) -> ApplyReport {
+    apply_with_progress(actions, &mut |_| {})
+}
+
+/// Like `apply`, calling `progress` after each action so that a caller can drive
+/// its own progress display.
+pub fn apply_with_progress(
+    actions: &[PlannedAction],
+    progress: &mut dyn FnMut(Progress),
+) -> ApplyReport {
+    let mut report = ApplyReport::default();
+    for (index, action) in actions.iter().enumerate() {
+        let result = {
+            let _permit = io_limit::acquire();
+            carry_out(action)
+        };
+
+        match result {
+            Ok(()) => {
+                log::debug!("{}", action);
+                report.record(action);
+            }
+            Err(error) => {
+                match action {
+                    PlannedAction::Hardlink { source, target, .. }
+                        if error.kind() == io::ErrorKind::CrossesDevices =>
+                    {
+                        log::error!(
+                            "Error creating hard link: {} and {} are on different devices, use symbolic links instead",
+                            source.display(),
+                            target.display()
+                        )
+                    }
+                    _ => log::error!("Error applying {}: {}", action, error),
+                }
+                report.errors.push(FileTreeError::io(
+                    action.target().display().to_string(),
+                    error,
+                ));
+            }
+        }
+        progress(Progress {
+            total: actions.len(),
+            completed: index + 1,
+            current_path: action.target(),
+        });
+    }
+    report
+}
+
+// Copies take real space, unlike links, so say how much before making them
+fn warn_copy_size(actions: &[PlannedAction]) {
+    let (count, bytes) = actions
+        .iter()
+        .filter_map(|action| match action {
+            PlannedAction::Copy { source, .. } => Some(source),
+            _ => None,
+        })
+        .fold((0, 0), |(count, bytes), source| {
+            let size = {
+                let _permit = io_limit::acquire();
+                fs::metadata(source).map_or(0, |metadata| metadata.len())
+            };
+            (count + 1, bytes + size)
+        });
+    if count > 0 {
+        log::warn!(
+            "Copying {} files takes {} of space in the destination",
+            count,
+            human_size(bytes)
+        );
+    }
+}
+
+// Verifies the links and copies `report` lists as created, adding the failures to
+// it, and returns the paths that passed. Symbolic links are expected to point to directories when `directories` is set.
+fn verify_created(
+    actions: &[PlannedAction],
+    report: &mut ApplyReport,
+    directories: bool,
+) -> Vec<PathBuf> {
+    let made: HashSet<&Path> = report
+        .created
+        .iter()
+        .chain(&report.replaced)
+        .map(PathBuf::as_path)
+        .collect();
+    let mut verified = Vec::new();
+    let mut failures = Vec::new();
+    for action in actions
+        .iter()
+        .filter(|action| made.contains(action.target()))
+    {
+        match verify(action, directories) {
+            Ok(true) => verified.push(action.target().to_path_buf()),
+            Ok(false) => {}
+            Err(error) => {
+                log::error!("{}", error);
+                failures.push(error);
+            }
+        }
+    }
+    report.errors.extend(failures);
+    verified
+}
+
+// Checks the link or copy created by `action`. Returns whether it was checked,
+// directories and `.nfo` files are not.
+fn verify(action: &PlannedAction, directories: bool) -> Result<bool, FileTreeError> {
+    let failed = |target: &Path, reason: String| FileTreeError::VerificationFailed {
+        path: target.display().to_string(),
+        reason,
+    };
+    let readable = |path: &Path| {
+        let _permit = io_limit::acquire();
+        fs::File::open(path).and_then(|file| file.metadata())
+    };
+    match action {
+        PlannedAction::Symlink { target, .. } if directories => {
+            let metadata = {
+                let _permit = io_limit::acquire();
+                fs::read_dir(target).and_then(|_| fs::metadata(target))
+            }
+            .map_err(|error| failed(target, format!("does not resolve: {}", error)))?;
+            if !metadata.is_dir() {
+                return Err(failed(target, "does not point to a directory".to_string()));
+            }
+        }
+        PlannedAction::Symlink { target, .. } => {
+            let metadata = readable(target)
+                .map_err(|error| failed(target, format!("does not resolve: {}", error)))?;
+            if !metadata.is_file() {
+                return Err(failed(target, "does not point to a file".to_string()));
+            }
+        }
+        PlannedAction::Hardlink { source, target, .. } => {
+            let metadata = readable(target).map_err(|error| failed(target, error.to_string()))?;
+            let source_metadata =
+                readable(source).map_err(|error| failed(target, format!("source: {}", error)))?;
+            if !same_file(&metadata, &source_metadata) {
+                return Err(failed(target, "is not a link to its source".to_string()));
+            }
+        }
+        PlannedAction::Copy { source, target, .. } => {
+            let hash =
+                dedupe::full_hash(target).map_err(|error| failed(target, error.to_string()))?;
+            let source_hash = dedupe::full_hash(source)
+                .map_err(|error| failed(target, format!("source: {}", error)))?;
+            if hash != source_hash {
+                return Err(failed(
+                    target,
+                    "content differs from the source".to_string(),
+                ));
+            }
+        }
+        PlannedAction::CreateDir { .. } | PlannedAction::WriteNfo { .. } => return Ok(false),
+    }
+    Ok(true)
+}
+
+// Carries out a single action, with the long paths of Windows prefixed (see
+// `long_path`)
+fn carry_out(action: &PlannedAction) -> io::Result<()> {
+    let target = long_path(action.target())?;
+    match action {
+        PlannedAction::CreateDir { .. } => fs::create_dir_all(&target),
+        PlannedAction::Symlink {
+            source, replace, ..
+        } => {
+            remove_replaced(*replace, &target)?;
+            make_symlink(source, &target)
+        }
+        PlannedAction::Hardlink {
+            source, replace, ..
+        } => {
+            remove_replaced(*replace, &target)?;
+            fs::hard_link(long_path(source)?, &target)
+        }
+        PlannedAction::Copy {
+            source, replace, ..
+        } => {
+            remove_replaced(*replace, &target)?;
+            fs::copy(long_path(source)?, &target).map(|_| ())
+        }
+        PlannedAction::WriteNfo {
+            title,
+            season,
+            episode,
+            ..
+        } => fs::write(&target, nfo::episode_details(title, *season, *episode)),
+    }
+}
+
+// Removes the link or file a replacing action takes the place of
+fn remove_replaced(replace: bool, link: &Path) -> io::Result<()> {
+    if replace {
+        fs::remove_file(link)
+    } else {
+        Ok(())
+    }
+}
+
+/// State shared by the steps of `FileTree::plan`
+struct LinkRun<'a> {
+    destination: PathBuf,
+    options: &'a LinkOptions,
+    group_template: Option<GroupTemplate>,
+    renamer: Renamer,
+    show: String,
+    created_directories: HashSet<PathBuf>,
+    // Files left out because an identical or better file is linked instead
+    duplicates: HashSet<String>,
+    actions: Vec<PlannedAction>,
+    // Links that already exist and point to the right file
+    skipped: Vec<PathBuf>,
+    // Sources that vanished since the tree was built
+    missing: Vec<PathBuf>,
+    // Links kept out by an existing file or directory
+    conflicts: Vec<PathBuf>,
+    mappings: Vec<LinkMapping>,
+}
+
+impl LinkRun<'_> {
+    /// Plans the creation of `directory` unless it exists or already is planned.
+    fn ensure_directory(&mut self, directory: &Path) {
+        if self.created_directories.contains(directory) {
+            return;
+        }
+        let exists = {
+            let _permit = io_limit::acquire();
+            directory.exists()
+        };
+        if exists || !self.created_directories.insert(directory.to_path_buf()) {
+            return;
+        }
+        self.actions.push(PlannedAction::CreateDir {
+            target: directory.to_path_buf(),
+        });
+    }
+
+    /// Plans a link at `link` pointing to `source`. An existing link to the same
+    /// source is left alone and a link to another file is replaced. A source that
+    /// no longer exists is recorded as missing instead of getting a dangling link.
+    /// Returns whether the link is in place or planned.
+    fn link(&mut self, source: &Path, link: &Path) -> bool {
+        let source_exists = {
+            let _permit = io_limit::acquire();
+            fs::metadata(source).is_ok()
+        };
+        if !source_exists {
+            log::warn!("Skipping {}, which no longer exists", source.display());
+            self.missing.push(source.to_path_buf());
+            return false;
+        }
+
+        let mode = self.options.link_mode;
+        let stored = match mode {
+            LinkMode::Symlink => self.link_target(source, link),
+            LinkMode::Hardlink | LinkMode::Copy => source.to_path_buf(),
+        };
+        let conflicts = self.conflicts.len();
+        let existing = match mode {
+            LinkMode::Symlink => self.existing_symlink(&stored, link),
+            LinkMode::Hardlink => self.existing_hardlink(source, link),
+            LinkMode::Copy => self.existing_copy(source, link),
+        };
+        let Some(replace) = existing else {
+            // Skipped links are in place, conflicting ones are not
+            return self.conflicts.len() == conflicts;
+        };
+
+        let (source, target) = (stored, link.to_path_buf());
+        self.actions.push(match mode {
+            LinkMode::Symlink => PlannedAction::Symlink {
+                source,
+                target,
+                replace,
+            },
+            LinkMode::Hardlink => PlannedAction::Hardlink {
+                source,
+                target,
+                replace,
+            },
+            LinkMode::Copy => PlannedAction::Copy {
+                source,
+                target,
+                replace,
+            },
+        });
+        true
+    }
+
+    /// Inspects the symbolic link at `link`. Returns `None` (recording a skip) when it
+    /// already points to `target`, and whether it has to be replaced otherwise.
+    fn existing_symlink(&mut self, target: &Path, link: &Path) -> Option<bool> {
+        let existing = {
+            let _permit = io_limit::acquire();
+            fs::read_link(link)
+        };
+        match existing {
+            Ok(existing) if existing == target => {
+                self.skipped.push(link.to_path_buf());
+                None
+            }
+            Ok(existing) => {
+                log::info!(
+                    "Relinking {}: {} -> {}",
+                    link.display(),
+                    existing.display(),
+                    target.display()
+                );
+                Some(true)
+            }
+            Err(_) => self.entry_in_the_way(link),
+        }
+    }
+
+    /// Inspects the entry at `link`. Returns `None` (recording a skip) when it already
+    /// is a hard link to `source`, and whether it has to be replaced otherwise.
+    /// Symbolic links (e.g. left by a symlink run) are replaced, other files only
+    /// with `LinkOptions::force`.
+    fn existing_hardlink(&mut self, source: &Path, link: &Path) -> Option<bool> {
+        let (existing, source_metadata) = {
+            let _permit = io_limit::acquire();
+            (fs::symlink_metadata(link), fs::metadata(source))
+        };
+        match (existing, source_metadata) {
+            (Ok(existing), _) if existing.file_type().is_symlink() => {
+                log::info!(
+                    "Relinking {} as a hard link to {}",
+                    link.display(),
+                    source.display()
+                );
+                Some(true)
+            }
+            (Ok(existing), Ok(source_metadata)) if same_file(&existing, &source_metadata) => {
+                self.skipped.push(link.to_path_buf());
+                None
+            }
+            _ => self.entry_in_the_way(link),
+        }
+    }
+
+    /// Inspects the entry at `link`. Returns `None` (recording a skip) when it already
+    /// is a copy of `source`, judged by its size, and whether it has to be replaced
+    /// otherwise. A link is replaced; a file of another size, e.g. an interrupted
+    /// copy, is only replaced with `LinkOptions::force`, see `entry_in_the_way`.
+    fn existing_copy(&mut self, source: &Path, link: &Path) -> Option<bool> {
+        let (existing, source_metadata) = {
+            let _permit = io_limit::acquire();
+            (fs::symlink_metadata(link), fs::metadata(source))
+        };
+        match (existing, source_metadata) {
+            (Ok(existing), Ok(source_metadata))
+                if existing.is_file() && existing.len() == source_metadata.len() =>
+            {
+                self.skipped.push(link.to_path_buf());
+                None
+            }
+            (Ok(existing), _) if existing.is_symlink() => {
+                log::info!("Copying {} over {}", source.display(), link.display());
+                Some(true)
+            }
+            _ => self.entry_in_the_way(link),
+        }
+    }
+
+    /// Inspects the entry at `link`, which is not a link to keep or replace. Returns
+    /// `Some(false)` when there is none, and `Some(true)` for a regular file to delete
+    /// with `LinkOptions::force`. Returns `None` otherwise, recording a conflict.
+    fn entry_in_the_way(&mut self, link: &Path) -> Option<bool> {
+        let existing = {
+            let _permit = io_limit::acquire();
+            fs::symlink_metadata(link)
+        };
+        match existing {
+            Err(_) => Some(false),
+            Ok(existing) if existing.is_file() && self.options.force => {
+                log::warn!("Deleting the file {} to link in its place", link.display());
+                Some(true)
+            }
+            Ok(existing) => {
+                let entry = if existing.is_dir() {
+                    "directory"
+                } else {
+                    "file"
+                };
+                log::warn!("Not linking {}, a {} is in the way", link.display(), entry);
+                self.conflicts.push(link.to_path_buf());
+                None
+            }
+        }
+    }
+
+    /// The name of the link of a media file named `name` by the run, see
+    /// `LinkOptions::rename_patterns` and `LinkOptions::normalize_extension_case`.
+    fn link_name(&self, name: &str) -> String {
+        let name = self.renamer.rename(name);
+        sanitize_link_name(&self.extension_case(&name))
+    }
+
+    /// `name` with its extension lowercased when the run normalizes extensions,
+    /// see `LinkOptions::normalize_extension_case`.
+    fn extension_case<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match name.rfind('.') {
+            Some(dot) if self.options.normalize_extension_case => {
+                Cow::Owned(format!("{}{}", &name[..dot], name[dot..].to_lowercase()))
+            }
+            _ => Cow::Borrowed(name),
+        }
+    }
+
+    /// Plans an `.nfo` file describing the episode next to its link.
+    fn write_nfo(&mut self, link: &Path, title: &str, season: u32, episode: u32) {
+        self.actions.push(PlannedAction::WriteNfo {
+            target: link.with_extension("nfo"),
+            title: title.to_string(),
+            season,
+            episode,
+        });
+    }
+
+    /// Returns the path the link at `link` should store to reach `source`: the
+    /// path relative to the link's directory in relative mode, `source` otherwise.
+    fn link_target(&self, source: &Path, link: &Path) -> PathBuf {
+        if !self.options.relative {
+            return source.to_path_buf();
+        }
+
+        match link
+            .parent()
+            .and_then(|directory| relative_path(directory, source))
+        {
+            Some(target) => target,
+            None => {
+                log::warn!(
+                    "{} and {} do not share a root, using an absolute link",
+                    source.display(),
+                    link.display()
+                );
+                source.to_path_buf()
+            }
+        }
+    }
+}
+
+/// A media file to link along with the directory holding it (and its subtitles)
+type Episode<'a> = (&'a FileTree, &'a str);
+
+impl FileTree {
+    /// Creates links to the media files of the tree inside `destination`.
+    /// The files are bucketed into seasons according to `options.group_by`, the
+    /// seasons being numbered from 1 and the episodes of each season from 1 in
+    /// `options.sort_order` (natural by default), unless `options.season_start` and
+    /// `options.episode_start` say otherwise. Season and episode numbers found
+    /// in the file name (see `parse_season_episode`) take precedence over these
+    /// positions.
+    ///
+    /// With `GroupingType::Original` the links are created directly in `destination`
+    /// and named `SxxEyy - <file name>`. With `GroupingType::Plex` they are placed in
+    /// `Season xx` directories and named `<show> - SxxEyy.<ext>`, the show being the
+    /// name of the tree. `options.group_template` overrides the season directories.
+    /// Link names are made safe for any filesystem, see `sanitize_link_name`.
+    ///
+    /// Consecutive parts of a multi-part episode (see `split_part`) share its number
+    /// and are told apart by a suffix, e.g. `S01E02-part1` and `S01E02-part2`.
+    ///
+    /// Subtitles sharing the stem of a linked video (`Episode.srt`, `Episode.en.srt`)
+    /// are linked next to it under the new name, keeping their language suffix.
+    ///
+    /// This is `plan` followed by `apply`, or by logging the plan in a dry run.
+    ///
+    /// # Arguments
+    ///
+    /// * `destination` - The directory in which the links are created.
+    /// * `options` - The extensions to link, naming scheme, link mode and the
+    ///   other settings of the run (see `LinkOptions`).
+    ///
+    /// # Returns
+    ///
+    /// * A `SymlinkReport` listing the created, skipped and replaced links, and the
+    ///   ones that failed. Links that already point to the right file are skipped;
+    ///   links pointing elsewhere are replaced. A regular file or directory in the way
+    ///   of a link is reported as a conflict, or with `options.force` the file is
+    ///   deleted and replaced. A failed link does not stop the run.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `FileTreeError` of `plan`, before anything is created, when the
+    /// options are invalid, `destination` overlaps the tree's directory or, with
+    /// `options.strict`, a file of the tree no longer exists.
+    pub fn create_grouped_symlinks(
+        &self,
+        destination: String,
+        options: &LinkOptions,
+    ) -> Result<SymlinkReport, FileTreeError> {
+        self.create_grouped_symlinks_with_progress(destination, options, &mut |_| {})
+    }
+
+    /// Like `create_grouped_symlinks`, calling `progress` after each directory, link
+    /// or `.nfo` file is created (see `apply_with_progress`). A dry run creates
+    /// nothing and reports no progress.
+    pub fn create_grouped_symlinks_with_progress(
+        &self,
+        destination: String,
+        options: &LinkOptions,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<SymlinkReport, FileTreeError> {
+        let run = self.plan_run(Path::new(&destination), options)?;
+        warn_copy_size(&run.actions);
+
+        let mut verified = Vec::new();
+        let mut mappings = run.mappings;
+        let (report, planned) = if options.dry_run {
+            let mut report = ApplyReport::default();
+            for action in &run.actions {
+                log::info!("{}", action);
+                report.record(action);
+            }
+            (report, run.actions)
+        } else {
+            let mut report = apply_with_progress(&run.actions, progress);
+            if options.verify {
+                verified = verify_created(&run.actions, &mut report, options.link_directories);
+            }
+            let failed: HashSet<&str> = report
+                .errors
+                .iter()
+                .filter_map(|error| match error {
+                    FileTreeError::Io { path, .. } => Some(path.as_str()),
+                    _ => None,
+                })
+                .collect();
+            mappings
+                .retain(|mapping| !failed.contains(mapping.link.display().to_string().as_str()));
+            (report, Vec::new())
+        };
+        Ok(SymlinkReport {
+            directories: report.directories,
+            created: report.created,
+            skipped: run.skipped,
+            replaced: report.replaced,
+            missing: run.missing,
+            conflicts: run.conflicts,
+            verified,
+            failures: report.errors,
+            planned,
+            mappings,
+        })
+    }
+
+    /// Decides the directories, links and `.nfo` files `create_grouped_symlinks`
+    /// would create, without touching the filesystem. Existing links are inspected
+    /// so that the ones already pointing to the right file are left out. The actions
+    /// are meant to be reviewed, serialized or carried out by `apply`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `FileTreeError` of an invalid option (see `LinkOptions::validate`),
+    /// `FileTreeError::OverlappingPaths` when `destination` overlaps the tree's
+    /// directory (or one of the roots of a multi-root tree), or `FileTreeError::MissingSource` when `options.strict` is set and
+    /// a file of the tree no longer exists.
+    pub fn plan(
+        &self,
+        destination: impl AsRef<Path>,
+        options: &LinkOptions,
+    ) -> Result<Vec<PlannedAction>, FileTreeError> {
+        self.plan_run(destination.as_ref(), options)
+            .map(|run| run.actions)
+    }
+
+    fn plan_run<'a>(
+        &self,
+        destination: &Path,
+        options: &'a LinkOptions,
+    ) -> Result<LinkRun<'a>, FileTreeError> {
+        options.validate()?;
+        let roots = self.roots();
+        for root in &roots {
+            check_disjoint(Path::new(&root.path), destination)?;
+        }
+        let mut run = LinkRun {
+            destination: normalize_path(destination),
+            options,
+            group_template: options.parse_group_template()?,
+            renamer: Renamer::new(&options.rename_patterns)?,
+            // The first root names the show, like the first directory of a merge
+            show: roots
+                .first()
+                .map_or_else(|| self.name(), |root| root.name()),
+            created_directories: HashSet::new(),
+            duplicates: HashSet::new(),
+            actions: Vec::new(),
+            skipped: Vec::new(),
+            missing: Vec::new(),
+            conflicts: Vec::new(),
+            mappings: Vec::new(),
+        };
+        if options.dedupe {
+            for group in self.find_duplicates() {
+                for duplicate in &group[1..] {
+                    log::info!("Skipping {}, a duplicate of {}", duplicate, group[0]);
+                }
+                run.duplicates.extend(group.into_iter().skip(1));
+            }
+        }
+        if !options.quality_priority.is_empty() {
+            for (file, preferred) in self.find_lesser_qualities(options) {
+                log::info!("Skipping {}, {} is preferred", file, preferred);
+                run.duplicates.insert(file);
+            }
+        }
+
+        run.ensure_directory(&run.destination.clone());
+        if options.mirror {
+            self.link_mirror(&mut run);
+        } else if options.flatten {
+            self.link_flat(&mut run);
+        } else if options.link_directories {
+            self.link_directories(&mut run);
+        } else {
+            let mut groups = self.group_episodes(&run);
+            sort_groups(&mut groups, options.group_sort);
+            let specials = self.take_specials(&mut groups, &options.specials);
+            let season_start = options.season_start.unwrap_or(1);
+            for (index, (name, group)) in groups.into_iter().enumerate() {
+                let first_episode = match index {
+                    0 => options.episode_start.unwrap_or(1),
+                    _ => 1,
+                };
+                link_group(
+                    &mut run,
+                    &name,
+                    group,
+                    season_start + index as u32,
+                    first_episode,
+                );
+            }
+            if !specials.is_empty() {
+                link_group(&mut run, "Specials", specials, 0, 1);
+            }
+        }
+
+        if let (true, Some(missing)) = (options.strict, run.missing.first()) {
+            return Err(FileTreeError::MissingSource {
+                path: missing.display().to_string(),
+            });
+        }
+        Ok(run)
+    }
+
+    /// Links the media files of the whole tree directly in the destination, numbered
+    /// in the sort order of their paths.
+    fn link_flat(&self, run: &mut LinkRun) {
+        let mut episodes = Vec::new();
+        self.collect_episodes(run, &mut episodes);
+        run.options
+            .sort_order
+            .sort_by_key(&mut episodes, |(_, file)| file);
+
+        let width = episodes.len().to_string().len().max(4);
+        for (index, (directory, file)) in episodes.into_iter().enumerate() {
+            let file_name = Path::new(file).file_name().unwrap().to_string_lossy();
+            let link = run.destination.join(run.link_name(&format!(
+                "{:0width$} - {}",
+                index + 1,
+                file_name
+            )));
+            directory.link_with_sidecars(run, file, &link);
+        }
+    }
+
+    /// Links the media files of the whole tree at their path relative to the tree in
+    /// the destination, creating the directories holding them.
+    fn link_mirror(&self, run: &mut LinkRun) {
+        let mut episodes = Vec::new();
+        self.collect_episodes(run, &mut episodes);
+
+        let roots = self.roots();
+        let multi_root = self.path == MULTI_ROOT_PATH;
+        for (directory, file) in episodes {
+            // The files of each root of a multi-root tree go under the root's name
+            let relative = roots
+                .iter()
+                .find_map(|root| {
+                    let relative = Path::new(file).strip_prefix(&root.path).ok()?;
+                    if multi_root {
+                        Some(Path::new(&root.name()).join(relative))
+                    } else {
+                        Some(relative.to_path_buf())
+                    }
+                })
+                .unwrap_or_else(|| PathBuf::from(Path::new(file).file_name().unwrap()));
+            let link = run.destination.join(relative);
+            if let Some(parent) = link.parent() {
+                run.ensure_directory(parent);
+            }
+            directory.link_with_sidecars(run, file, &link);
+        }
+    }
+
+    /// The directories the tree was scanned from: the trees of the roots of a
+    /// multi-root tree (see `MULTI_ROOT_PATH`), the tree itself otherwise.
+    fn roots(&self) -> Vec<&FileTree> {
+        if self.path == MULTI_ROOT_PATH {
+            self.directories.iter().collect()
+        } else {
+            vec![self]
+        }
+    }
+
+    /// Links each directory holding media files as a whole, named like its season
+    /// directory, in season order.
+    fn link_directories(&self, run: &mut LinkRun) {
+        let mut groups = self.group_episodes(run);
+        sort_groups(&mut groups, run.options.group_sort);
+        let season_start = run.options.season_start.unwrap_or(1);
+        for (index, (name, group)) in groups.into_iter().enumerate() {
+            let season = season_start + index as u32;
+            let link_name = match &run.group_template {
+                Some(template) => {
+                    let kind = match run.options.grouping {
+                        GroupingType::Plex => "Season",
+                        GroupingType::Original => "Group",
+                    };
+                    template.render(season, &name, kind)
+                }
+                None => name,
+            };
+            let directory = group[0].0;
+            let link = run.destination.join(fit_name(&link_name, ""));
+            run.link(Path::new(&directory.path), &link);
+        }
+    }
+
+    /// Buckets the media files of the whole tree into named seasons, in season order.
+    fn group_episodes<'a>(&'a self, run: &LinkRun) -> Vec<(String, Vec<Episode<'a>>)> {
+        let mut episodes = Vec::new();
+        self.collect_episodes(run, &mut episodes);
+
+        match &run.options.group_by {
+            GroupBy::Filename => episodes
+                .into_iter()
+                .map(|episode| {
+                    let stem = Path::new(episode.1).file_stem().unwrap().to_string_lossy();
+                    (stem.into_owned(), vec![episode])
+                })
+                .collect(),
+            GroupBy::ParentDir => {
+                // Directories are walked one after the other, so their files are contiguous
+                let mut groups: Vec<(String, Vec<Episode>)> = Vec::new();
+                for episode in episodes {
+                    match groups.last_mut() {
+                        Some((_, group)) if std::ptr::eq(group[0].0, episode.0) => {
+                            group.push(episode)
+                        }
+                        _ => groups.push((episode.0.name(), vec![episode])),
+                    }
+                }
+                groups
+            }
+            GroupBy::Regex(pattern) => {
+                let regex = match compile_group_regex(pattern) {
+                    Ok(regex) => regex,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        return Vec::new();
+                    }
+                };
+
+                // Buckets are matched on their normalized key and named after their first file
+                let mut groups: Vec<(String, String, Vec<Episode>)> = Vec::new();
+                let mut ungrouped = Vec::new();
+                for episode in episodes {
+                    let file_name = Path::new(episode.1).file_name().unwrap().to_string_lossy();
+                    let Some(key) = regex
+                        .captures(&file_name)
+                        .and_then(|captures| captures.name(GROUP_CAPTURE))
+                    else {
+                        log::debug!("{} does not match {}, it is ungrouped", episode.1, pattern);
+                        ungrouped.push(episode);
+                        continue;
+                    };
+                    let normalized = normalize_group_key(key.as_str());
+                    match groups.iter_mut().find(|(group, _, _)| *group == normalized) {
+                        Some((_, _, group)) => group.push(episode),
+                        None => groups.push((normalized, key.as_str().to_string(), vec![episode])),
+                    }
+                }
+                run.options
+                    .sort_order
+                    .sort_by_key(&mut groups, |(key, _, _)| key);
+                let mut groups: Vec<(String, Vec<Episode>)> = groups
+                    .into_iter()
+                    .map(|(_, name, group)| (name, group))
+                    .collect();
+                if !ungrouped.is_empty() {
+                    groups.push(("Ungrouped".to_string(), ungrouped));
+                }
+                groups
+            }
+        }
+    }
+
+    /// Takes the specials (see `LinkOptions::specials`) out of `groups`, in group
+    /// order, and drops the groups left empty. The name of the tree itself is not
+    /// looked at, so that a show named after a keyword is not all specials.
+    fn take_specials<'a>(
+        &self,
+        groups: &mut Vec<(String, Vec<Episode<'a>>)>,
+        keywords: &[String],
+    ) -> Vec<Episode<'a>> {
+        if keywords.is_empty() {
+            return Vec::new();
+        }
+        let mut specials = Vec::new();
+        for (_, group) in groups.iter_mut() {
+            let (special, regular): (Vec<Episode>, Vec<Episode>) = std::mem::take(group)
+                .into_iter()
+                .partition(|(directory, file)| {
+                    let file_name = Path::new(file).file_name().unwrap().to_string_lossy();
+                    episode::is_special(&file_name, keywords)
+                        || (!std::ptr::eq(*directory, self)
+                            && episode::is_special(&directory.name(), keywords))
+                });
+            specials.extend(special);
+            *group = regular;
+        }
+        groups.retain(|(_, group)| !group.is_empty());
+        specials
+    }
+
+    /// Collects the media files of the tree, depth first and in the sort order of the run.
+    fn collect_episodes<'a>(&'a self, run: &LinkRun, episodes: &mut Vec<Episode<'a>>) {
+        let order = run.options.sort_order;
+        let options = run.options;
+        for file in self.generate_file_list(options, order) {
+            if !run.duplicates.contains(file) {
+                episodes.push((self, file));
+            }
+        }
+
+        let mut directories: Vec<&FileTree> = self.directories.iter().collect();
+        order.sort_by_key(&mut directories, |directory| &directory.path);
+        for directory in directories {
+            directory.collect_episodes(run, episodes);
+        }
+    }
+
+    /// Links `file`, a media file of this directory in the group `group`, along
+    /// with its subtitles. `part` is set for a part of a multi-part episode.
+    fn link_episode(
+        &self,
+        run: &mut LinkRun,
+        file: &str,
+        group: &str,
+        (season, episode): (u32, u32),
+        part: Option<u32>,
+    ) {
+        let file_name = Path::new(file).file_name().unwrap().to_string_lossy();
+        let extension = extension_of(file);
+        // Position of the group, whatever number its season was given, 0 for specials
+        let index = (season + 1).saturating_sub(run.options.season_start.unwrap_or(1));
+        let (season, episode) = match episode::parse_episode_numbers(&file_name) {
+            Some((parsed_season, episode)) => (parsed_season.unwrap_or(season), episode),
+            None => (season, episode),
+        };
+        let mut number = format!("S{:02}E{:02}", season, episode);
+        if let Some(part) = part {
+            number.push_str(&format!("-part{}", part));
+            log::warn!(
+                "{} looks like part {} of a multi-part episode, linking it as {}",
+                file,
+                part,
+                number
+            );
+        }
+
+        let season_path = match &run.group_template {
+            Some(template) => {
+                let kind = match run.options.grouping {
+                    GroupingType::Plex => "Season",
+                    GroupingType::Original => "Group",
+                };
+                let season_path = run
+                    .destination
+                    .join(fit_name(&template.render(season, group, kind), ""));
+                run.ensure_directory(&season_path);
+                season_path
+            }
+            None => run.destination.to_path_buf(),
+        };
+        let new_file_path = match run.options.grouping {
+            GroupingType::Original => {
+                let stem = &file_name[..file_name.len() - extension.len()];
+                season_path.join(run.link_name(&format!("{} - {}{}", number, stem, extension)))
+            }
+            GroupingType::Plex => {
+                season_path.join(run.link_name(&format!("{} - {}{}", run.show, number, extension)))
+            }
+        };
+
+        if !self.link_with_sidecars(run, file, &new_file_path) {
+            return;
+        }
+        run.mappings.push(LinkMapping {
+            group: group.to_string(),
+            index,
+            source: PathBuf::from(file),
+            link: new_file_path.clone(),
+            season,
+            episode,
+        });
+        // A companion `.nfo` is linked in place of the generated one
+        let has_nfo = self
+            .sidecars(file, run.options)
+            .iter()
+            .any(|(_, suffix)| suffix.eq_ignore_ascii_case(".nfo"));
+        if run.options.write_nfo && run.options.grouping == GroupingType::Plex && !has_nfo {
+            run.write_nfo(&new_file_path, &file_name, season, episode);
+        }
+    }
+
+    /// Links `file`, a media file of this directory, at `link` and its subtitles and
+    /// companion files next to it under the same stem. Returns whether `file` is
+    /// linked.
+    fn link_with_sidecars(&self, run: &mut LinkRun, file: &str, link: &Path) -> bool {
+        if !run.link(Path::new(file), link) {
+            return false;
+        }
+        for (sidecar, suffix) in self.sidecars(file, run.options) {
+            let link_name = link.file_name().unwrap().to_string_lossy();
+            let link_stem = &link_name[..link_name.len() - extension_of(&link_name).len()];
+            let suffix = run.extension_case(suffix);
+            let sidecar_path = link.with_file_name(fit_name(link_stem, &suffix));
+            run.link(Path::new(sidecar), &sidecar_path);
+        }
+        true
+    }
+
+    /// Returns the subtitle and companion files (see `LinkOptions::companion_extensions`)
+    /// of this directory that belong to `video`, i.e. that share its stem
+    /// (`Episode.srt`, `Episode.en.srt`, `Episode.jpg`, ...), along with the part of
+    /// their name following the stem (`.srt`, `.en.srt`, `.jpg`, ...).
+    fn sidecars<'a>(&'a self, video: &str, options: &LinkOptions) -> Vec<(&'a str, &'a str)> {
+        let stem = &video[..video.len() - extension_of(video).len()];
+        self.files
+            .iter()
+            .filter(|file| file.len() > stem.len() && file.starts_with(stem))
+            .map(|file| (file.as_str(), &file[stem.len()..]))
+            .filter(|(_, suffix)| {
+                let suffix = suffix.to_lowercase();
+                suffix.starts_with('.')
+                    && (SUBTITLE_EXTENSIONS
+                        .iter()
+                        .any(|extension| suffix.ends_with(extension))
+                        || options
+                            .companion_extensions
+                            .iter()
+                            .any(|extension| suffix.ends_with(&extension.to_lowercase())))
+            })
+            .collect()
+    }
+}
+
+// Links the episodes of `group` in `season`, numbered from `first_episode`
+fn link_group(run: &mut LinkRun, name: &str, group: Vec<Episode>, season: u32, first_episode: u32) {
+    let mut next_episode = first_episode;
+    // Consecutive parts of one episode (`part1`, `part2`) share its number
+    let mut episode = next_episode;
+    let mut previous_rest = None;
+    for (directory, file) in group {
+        let file_name = Path::new(file).file_name().unwrap().to_string_lossy();
+        let part = episode::split_part(&file_name);
+        let rest = part.as_ref().map(|(rest, _)| rest.clone());
+        if rest.is_none() || rest != previous_rest {
+            episode = next_episode;
+            next_episode += 1;
+        }
+        previous_rest = rest;
+        directory.link_episode(
+            run,
+            file,
+            name,
+            (season, episode),
+            part.map(|(_, part)| part),
+        );
+    }
+}
+
+/// Orders the named `groups` of episodes by `sort`. Files that cannot be read
+/// count as empty and as never modified.
+fn sort_groups(groups: &mut [(String, Vec<Episode>)], sort: GroupSort) {
+    let metadata = |file: &str| {
+        let _permit = io_limit::acquire();
+        fs::metadata(file).ok()
+    };
+    match sort {
+        GroupSort::Name => {}
+        GroupSort::Count => groups.sort_by_key(|(_, group)| group.len()),
+        GroupSort::Size => groups.sort_by_cached_key(|(_, group)| {
+            group
+                .iter()
+                .filter_map(|(_, file)| metadata(file))
+                .map(|metadata| metadata.len())
+                .sum::<u64>()
+        }),
+        GroupSort::Mtime => groups.sort_by_cached_key(|(_, group)| {
+            group
+                .iter()
+                .filter_map(|(_, file)| metadata(file)?.modified().ok())
+                .max()
+        }),
+    }
+}
+
+/// Makes `name` safe to use as a link name on any common filesystem: the characters
+/// illegal on some of them (`/`, `\`, `:`, `*`, `?`, ...) and control characters are
+/// replaced by `_`, and the name is truncated to 255 bytes, keeping its extension.
+///
+/// ```
+/// use file_tree::sanitize_link_name;
+///
+/// assert_eq!(sanitize_link_name("Re:Zero? - S01E01.mkv"), "Re_Zero_ - S01E01.mkv");
+/// ```
+pub fn sanitize_link_name(name: &str) -> String {
+    let extension = extension_of(name);
+    fit_name(&name[..name.len() - extension.len()], extension)
+}
+
+/// Joins `stem` and `suffix` into a sanitized name, truncating `stem` so the name
+/// fits in `MAX_NAME_BYTES`.
+fn fit_name(stem: &str, suffix: &str) -> String {
+    let replace_illegal = |text: &str| -> String {
+        text.chars()
+            .map(|c| {
+                if c.is_control() || ILLEGAL_NAME_CHARACTERS.contains(&c) {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect()
+    };
+    let stem = replace_illegal(stem);
+    let suffix = replace_illegal(suffix);
+
+    let mut end = MAX_NAME_BYTES.saturating_sub(suffix.len()).min(stem.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end < stem.len() {
+        log::debug!("Truncating the link name {}{}", stem, suffix);
+    }
+    format!("{}{}", stem[..end].trim_end(), suffix)
+}
+
+/// Checks that `destination` is not `source`, nested in it or one of its parents,
+/// so that the links do not end up indexed by a later scan of `source`. Both paths
+/// are canonicalized, the part of a path that does not exist yet being appended as is.
+///
+/// # Errors
+///
+/// Returns `FileTreeError::OverlappingPaths` when the paths overlap, or
+/// `FileTreeError::Io` when one of them cannot be resolved.
+pub fn check_disjoint(source: &Path, destination: &Path) -> Result<(), FileTreeError> {
+    let resolve = |path: &Path| {
+        canonicalize_existing(path)
+            .map_err(|error| FileTreeError::io(path.display().to_string(), error))
+    };
+    let (canonical_source, canonical_destination) = (resolve(source)?, resolve(destination)?);
+    if canonical_destination.starts_with(&canonical_source)
+        || canonical_source.starts_with(&canonical_destination)
+    {
+        return Err(FileTreeError::OverlappingPaths {
+            base: source.display().to_string(),
+            destination: destination.display().to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Canonicalizes the longest existing ancestor of `path` and appends the rest of it
+fn canonicalize_existing(path: &Path) -> io::Result<PathBuf> {
+    let path = normalize_lexically(&std::path::absolute(path)?);
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    loop {
+        let canonical = {
+            let _permit = io_limit::acquire();
+            existing.canonicalize()
+        };
+        match (canonical, existing.parent(), existing.file_name()) {
+            (Ok(canonical), _, _) => {
+                return Ok(missing
+                    .iter()
+                    .rev()
+                    .fold(canonical, |canonical, name| canonical.join(name)))
+            }
+            (Err(error), _, _) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+            (Err(_), Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            (Err(error), _, _) => return Err(error),
+        }
+    }
+}
+
+/// Computes the path leading from the directory `from` to `to`, e.g. `../Show/a.mp4`.
+/// Both paths are made absolute and lexically normalized first.
+///
+/// # Returns
+///
+/// * `None` when the paths live on different roots (e.g. two Windows drives).
+pub(crate) fn relative_path(from: &Path, to: &Path) -> Option<PathBuf> {
+    let from = normalize_lexically(&std::path::absolute(from).ok()?);
+    let to = normalize_lexically(&std::path::absolute(to).ok()?);
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+
+    if from.first() != to.first() {
+        return None;
+    }
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+// Resolves `.` and `..` components without touching the filesystem
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Whether both metadata describe the same file, i.e. are hard links to each other.
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Whether both metadata describe the same file. The file identity is not exposed
+/// on this platform, so files of the same size and modification time are assumed
+/// to be hard links to each other.
+#[cfg(not(unix))]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    a.len() == b.len() && a.modified().ok() == b.modified().ok()
+}
+
+/// Creates a symbolic link at `dst` pointing to `src`.
+#[cfg(unix)]
+fn make_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(src, dst)
+}
+
+/// Creates a symbolic link at `dst` pointing to `src`. Windows distinguishes
+/// between file and directory links, so the kind is picked from `src`, resolved
+/// against the link's directory when relative. An absolute `src` longer than
+/// `MAX_PATH` is stored with the `\\?\` prefix.
+#[cfg(windows)]
+fn make_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    // Prefixed paths are not normalized by Windows, so `..` is resolved here
+    let resolved = match dst.parent() {
+        Some(parent) => normalize_lexically(&parent.join(src)),
+        None => src.to_path_buf(),
+    };
+    let src = if src.is_absolute() {
+        long_path(src)?
+    } else {
+        Cow::Borrowed(src)
+    };
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(src, dst)
+    } else {
+        std::os::windows::fs::symlink_file(src, dst)
+    }
+}
+
+/// Returns `path` as is: only Windows limits the length of paths.
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> io::Result<Cow<'_, Path>> {
+    Ok(Cow::Borrowed(path))
+}
+
+/// Longest path, in UTF-16 units, that Windows accepts without the `\\?\` prefix
+/// (`MAX_PATH` less the terminating null)
+#[cfg(windows)]
+const MAX_PATH: usize = 259;
+
+/// Returns `path` as is when Windows accepts its length, and prefixed with `\\?\`
+/// (`\\?\UNC\` for a network share), which lifts the limit, otherwise.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error naming `path` when it is too long and relative,
+/// as only absolute paths can be prefixed.
+#[cfg(windows)]
+fn long_path(path: &Path) -> io::Result<Cow<'_, Path>> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Prefix;
+
+    if path.as_os_str().encode_wide().count() <= MAX_PATH {
+        return Ok(Cow::Borrowed(path));
+    }
+    if !path.is_absolute() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is longer than the {} characters Windows allows, use an absolute destination",
+                path.display(),
+                MAX_PATH
+            ),
+        ));
+    }
+
+    // Prefixed paths are taken verbatim, so `.` and `..` are resolved here
+    let normalized = normalize_lexically(path);
+    let mut components = normalized.components();
+    let mut prefixed = std::ffi::OsString::from(r"\\?\");
+    match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => prefixed.push(prefix.as_os_str()),
+            Prefix::UNC(server, share) => {
+                prefixed.push(r"UNC\");
+                prefixed.push(server);
+                prefixed.push(r"\");
+                prefixed.push(share);
+            }
+            // Already verbatim, or a device path
+            _ => return Ok(Cow::Owned(normalized)),
+        },
+        _ => return Ok(Cow::Owned(normalized)),
+    }
+    let mut prefixed = PathBuf::from(prefixed);
+    prefixed.extend(components);
+    Ok(Cow::Owned(prefixed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_path_climbs_to_the_common_ancestor() {
+        let relative = relative_path(
+            Path::new("/media/library/Season 01"),
+            Path::new("/media/shows/Show/a.mp4"),
+        );
+        assert_eq!(relative, Some(PathBuf::from("../../shows/Show/a.mp4")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_relative_path_needs_a_common_root() {
+        assert_eq!(
+            relative_path(Path::new("D:\\library"), Path::new("C:\\shows\\a.mp4")),
+            None
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Compares two strings in "natural" (human) order: runs of digits are compared
+/// by their numeric value, so `file2.mp4` sorts before `file10.mp4`. Strings that
+/// only differ by leading zeros fall back to a plain comparison to keep the order total.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let ordering = compare_numbers(&take_digits(&mut left), &take_digits(&mut right));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(l), Some(r)) => {
+                if l != r {
+                    return l.cmp(&r);
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+/// Order in which seasons and episodes are numbered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// Plain string order, `file10.mp4` before `file2.mp4`
+    Alphabetical,
+    /// Natural order (see `natural_cmp`), `file2.mp4` before `file10.mp4`
+    #[default]
+    Natural,
+    /// The order of the tree: a scanned directory keeps its files in on-disk order
+    /// and its subdirectories in natural order
+    AsScanned,
+}
+
+impl SortOrder {
+    /// Sorts `items` by the string `key` returns, leaving them as is for `AsScanned`.
+    pub(crate) fn sort_by_key<T>(self, items: &mut [T], key: impl Fn(&T) -> &str) {
+        match self {
+            SortOrder::Alphabetical => items.sort_by(|a, b| key(a).cmp(key(b))),
+            SortOrder::Natural => items.sort_by(|a, b| natural_cmp(key(a), key(b))),
+            SortOrder::AsScanned => {}
+        }
+    }
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+// Compares two digit runs numerically without parsing, so arbitrarily long runs work
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
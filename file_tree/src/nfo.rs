@@ -0,0 +1,29 @@
+/// Renders a minimal Kodi/Plex `<episodedetails>` document.
+pub(crate) fn episode_details(title: &str, season: u32, episode: u32) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <episodedetails>\n  \
+         <title>{}</title>\n  \
+         <season>{}</season>\n  \
+         <episode>{}</episode>\n\
+         </episodedetails>\n",
+        escape_xml(title),
+        season,
+        episode
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
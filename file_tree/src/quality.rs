@@ -0,0 +1,93 @@
+use crate::{extension_of, FileTree, LinkOptions, SortOrder};
+use std::collections::HashMap;
+use std::path::Path;
+
+impl FileTree {
+    /// Finds the media files of a run with `options` having a better copy next to
+    /// them, e.g. `Episode.720p.mp4` next to `Episode.1080p.mkv`. Files of a
+    /// directory are copies of the same episode when their names match once the
+    /// `options.quality_priority` markers and the extension are removed. Of each set
+    /// of copies, the file containing the earliest marker (case-insensitive, e.g.
+    /// `["2160p", "1080p", ".mkv"]`) is kept, the first in natural order on a tie.
+    ///
+    /// # Returns
+    ///
+    /// * The files left out, each with the file kept in its place.
+    pub(crate) fn find_lesser_qualities(&self, options: &LinkOptions) -> Vec<(String, String)> {
+        let priority: Vec<String> = options
+            .quality_priority
+            .iter()
+            .map(|marker| marker.to_lowercase())
+            .collect();
+        let mut lesser = Vec::new();
+        self.collect_lesser_qualities(options, &priority, &mut lesser);
+        lesser
+    }
+
+    fn collect_lesser_qualities(
+        &self,
+        options: &LinkOptions,
+        priority: &[String],
+        lesser: &mut Vec<(String, String)>,
+    ) {
+        let mut copies: HashMap<String, Vec<&str>> = HashMap::new();
+        let mut episodes = Vec::new();
+        for file in self.generate_file_list(options, SortOrder::Natural) {
+            let episode = episode_key(file, priority);
+            if !copies.contains_key(&episode) {
+                episodes.push(episode.clone());
+            }
+            copies.entry(episode).or_default().push(file);
+        }
+
+        for episode in episodes {
+            let files = &copies[&episode];
+            if files.len() < 2 {
+                continue;
+            }
+            // `min_by_key` keeps the first of equally ranked files
+            let best = files
+                .iter()
+                .min_by_key(|file| quality_rank(file, priority))
+                .unwrap();
+            lesser.extend(
+                files
+                    .iter()
+                    .filter(|file| *file != best)
+                    .map(|file| (file.to_string(), best.to_string())),
+            );
+        }
+
+        for directory in &self.directories {
+            directory.collect_lesser_qualities(options, priority, lesser);
+        }
+    }
+}
+
+// The lowercased name of `file` without its extension, `priority` markers and the
+// separators they leave behind
+fn episode_key(file: &str, priority: &[String]) -> String {
+    let name = Path::new(file).file_name().unwrap().to_string_lossy();
+    let mut key = name[..name.len() - extension_of(&name).len()].to_lowercase();
+    for marker in priority {
+        key = key.replace(marker.as_str(), "");
+    }
+    key.split(['.', ' ', '_', '-', '[', ']', '(', ')'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Position of the first `priority` marker found in the name of `file`, files
+// without any marker coming last
+fn quality_rank(file: &str, priority: &[String]) -> usize {
+    let name = Path::new(file)
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_lowercase();
+    priority
+        .iter()
+        .position(|marker| name.contains(marker.as_str()))
+        .unwrap_or(priority.len())
+}
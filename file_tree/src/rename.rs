@@ -0,0 +1,75 @@
+use crate::{extension_of, FileTreeError};
+use regex::Regex;
+use std::str::FromStr;
+
+/// A rewrite of link names, written `regex=>replacement`, e.g. `\[[^]]*\] *=>` to
+/// strip tags such as `[HorribleSubs]` or `\.?(720|1080)p=>` to drop resolutions.
+/// Every match in the name, extension excluded, is replaced; the replacement may
+/// refer to capture groups as `$1` or `${name}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenamePattern {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+impl RenamePattern {
+    pub(crate) fn compile(&self) -> Result<Regex, FileTreeError> {
+        Regex::new(&self.pattern).map_err(|source| FileTreeError::InvalidRegex {
+            pattern: self.pattern.clone(),
+            source,
+        })
+    }
+}
+
+impl FromStr for RenamePattern {
+    type Err = FileTreeError;
+
+    /// Parses `regex=>replacement`, splitting at the first `=>`, and checks the regex.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (pattern, replacement) =
+            text.split_once("=>")
+                .ok_or_else(|| FileTreeError::InvalidRenamePattern {
+                    text: text.to_string(),
+                })?;
+        let rename = RenamePattern {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        };
+        rename.compile()?;
+        Ok(rename)
+    }
+}
+
+/// `RenamePattern`s compiled once per run, applied in order
+pub(crate) struct Renamer {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Renamer {
+    pub(crate) fn new(patterns: &[RenamePattern]) -> Result<Self, FileTreeError> {
+        let rules = patterns
+            .iter()
+            .map(|pattern| Ok((pattern.compile()?, pattern.replacement.clone())))
+            .collect::<Result<_, FileTreeError>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Rewrites the link name `name`, keeping its extension and trimming the spaces
+    /// left at either end. A rewrite leaving nothing of the name is ignored.
+    pub(crate) fn rename(&self, name: &str) -> String {
+        if self.rules.is_empty() {
+            return name.to_string();
+        }
+        let extension = extension_of(name);
+        let mut stem = name[..name.len() - extension.len()].to_string();
+        for (regex, replacement) in &self.rules {
+            stem = regex.replace_all(&stem, replacement.as_str()).into_owned();
+        }
+        let stem = stem.trim();
+        if stem.is_empty() {
+            log::warn!("Keeping the name {}, renaming would leave it empty", name);
+            return name.to_string();
+        }
+        format!("{}{}", stem, extension)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::io_limit;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the ignore file read in every scanned directory. Its entries, and the
+/// ones of its subdirectories, matching a pattern of the file are skipped; the
+/// patterns of the files of nested directories add up.
+///
+/// The file holds one glob per line, matched against the paths relative to its
+/// directory. Blank lines and lines starting with `#` are skipped. Like in a
+/// `.gitignore`, a pattern without `/` (e.g. `*.nfo` or `Extras`) matches an entry
+/// at any depth, while a pattern with one (e.g. `Season 1/Extras`) only matches
+/// from that directory; a leading `/` just anchors the pattern. Negated patterns
+/// (`!`) are not supported.
+pub const IGNORE_FILE_NAME: &str = ".ripignore";
+
+/// The patterns of a `.ripignore` file, see `IGNORE_FILE_NAME`
+pub(crate) struct IgnoreFile {
+    directory: PathBuf,
+    patterns: GlobSet,
+}
+
+impl IgnoreFile {
+    /// Reads the `.ripignore` of `directory`. Returns `None` when there is none or it
+    /// holds no valid pattern; unreadable files and invalid patterns are skipped with
+    /// a warning.
+    pub(crate) fn read(directory: &str) -> Option<Self> {
+        let contents = {
+            let _permit = io_limit::acquire();
+            fs::read_to_string(Path::new(directory).join(IGNORE_FILE_NAME))
+        };
+        Self::from_contents(directory, contents)
+    }
+
+    /// Parses `contents`, the result of reading the `.ripignore` of `directory`, like
+    /// `read` does.
+    pub(crate) fn from_contents(directory: &str, contents: io::Result<String>) -> Option<Self> {
+        let path = Path::new(directory).join(IGNORE_FILE_NAME);
+        let contents = match contents {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return None,
+            Err(error) => {
+                log::warn!("Cannot read {}: {}", path.display(), error);
+                return None;
+            }
+        };
+
+        let mut builder = GlobSetBuilder::new();
+        let mut empty = true;
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let pattern = line.trim_start_matches('/').trim_end_matches('/');
+            let patterns = if line.trim_end_matches('/').contains('/') {
+                vec![pattern.to_string()]
+            } else {
+                vec![pattern.to_string(), format!("**/{}", pattern)]
+            };
+            for pattern in patterns {
+                match Glob::new(&pattern) {
+                    Ok(glob) => {
+                        builder.add(glob);
+                        empty = false;
+                    }
+                    Err(error) => {
+                        log::warn!("Skipping {} in {}: {}", line, path.display(), error);
+                        break;
+                    }
+                }
+            }
+        }
+        if empty {
+            return None;
+        }
+        match builder.build() {
+            Ok(patterns) => Some(IgnoreFile {
+                directory: PathBuf::from(directory),
+                patterns,
+            }),
+            Err(error) => {
+                log::warn!("Skipping {}: {}", path.display(), error);
+                None
+            }
+        }
+    }
+
+    /// Whether `path`, below the directory of the file, matches one of its patterns.
+    pub(crate) fn is_match(&self, path: &str) -> bool {
+        Path::new(path)
+            .strip_prefix(&self.directory)
+            .is_ok_and(|relative| self.patterns.is_match(relative))
+    }
+}
@@ -0,0 +1,541 @@
+use crate::ripignore::IgnoreFile;
+use crate::{
+    build_glob_set, io_limit, is_hidden_or_junk, natural_cmp, normalize_path, DirectoryEntries,
+    FileTree, FileTreeError, MULTI_ROOT_PATH,
+};
+use globset::GlobSet;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Options of a directory scan, set with chainable setters or deserialized
+/// (e.g. from a `[scan]` table with `max-depth = 2` and `exclude = [...]`). A
+/// config can be reused for any number of scans. Whatever the config, the
+/// `.ripignore` files found in the tree are honored, see `IGNORE_FILE_NAME`.
+///
+/// ```no_run
+/// use file_tree::ScanConfig;
+///
+/// let config = ScanConfig::new()
+///     .extensions([".mkv", ".srt"])
+///     .max_depth(2)
+///     .exclude(["**/extras/**"]);
+/// let tree = config.build_from("/media/Show").unwrap();
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ScanConfig {
+    #[serde(deserialize_with = "lowercase")]
+    extensions: Vec<String>,
+    max_depth: Option<usize>,
+    exclude: Vec<String>,
+    include: Vec<String>,
+    include_hidden: bool,
+    follow_symlinks: bool,
+    threads: usize,
+    #[serde(deserialize_with = "seconds")]
+    scan_timeout: Option<Duration>,
+    max_files: Option<usize>,
+}
+
+impl ScanConfig {
+    /// Creates a config scanning every file without depth limit, skipping hidden
+    /// and junk entries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps the files having one of `extensions` (e.g. `.mkv`), matched
+    /// case-insensitively. Subtitle extensions have to be listed for their
+    /// files to be linked next to the videos. All files are kept by default.
+    pub fn extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extensions = extensions
+            .into_iter()
+            .map(|extension| extension.into().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Descends at most `max_depth` levels below the scanned directory (`0` only
+    /// reads the directory itself).
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Skips the files and directories whose full path matches one of the glob
+    /// `patterns` (e.g. `**/extras/**`).
+    pub fn exclude<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only keeps the files whose full path matches one of the glob `patterns`
+    /// (e.g. `**/Season*/*.mkv`). Directories are still descended into. Excludes
+    /// take precedence: a file matching both an include and an exclude pattern is
+    /// skipped. All files are kept when no include pattern is given.
+    pub fn include<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.include.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Also indexes dotfiles and the well-known junk entries of `JUNK_NAMES`
+    /// (`@eaDir`, `.AppleDouble`, ...).
+    pub fn include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Descends into symbolic links to directories and indexes symbolic links to
+    /// files. A link leading back to one of the directories being scanned is
+    /// skipped, so cyclic links terminate. When unset (the default) every symbolic
+    /// link is skipped.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Scans subdirectories on a pool of `threads` threads instead of rayon's global
+    /// pool, which has one per CPU. `1` scans one directory at a time, e.g. for a NAS
+    /// or spinning disks that slow down under concurrent reads; `0` (the default)
+    /// keeps the global pool. Without the `parallel` feature scans are sequential
+    /// and this has no effect.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Gives up on a directory whose entries cannot be read within `timeout`, e.g. on
+    /// a hung network mount where reading would block forever. The directory is read
+    /// on a worker thread, left behind if it never returns. A subdirectory that times
+    /// out is left out of the tree with a warning (see `build_from_with_errors`); the
+    /// scanned directory itself timing out fails the scan. In the `[scan]` table of a
+    /// config the timeout is given in seconds, e.g. `scan-timeout = 30`.
+    pub fn scan_timeout(mut self, timeout: Duration) -> Self {
+        self.scan_timeout = Some(timeout);
+        self
+    }
+
+    /// Stops the scan with `FileTreeError::TooManyFiles` once more than `limit` files
+    /// are kept, naming the directory being read, as a guard against scanning a
+    /// whole disk by mistake. Scans are unlimited by default.
+    pub fn max_files(mut self, limit: usize) -> Self {
+        self.max_files = Some(limit);
+        self
+    }
+
+    /// Scans the directory at `path` with this config.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FileTreeError` naming the first directory or entry that could not be
+    /// read, or the first invalid exclude or include pattern.
+    pub fn build_from(&self, path: impl Into<String>) -> Result<FileTree, FileTreeError> {
+        self.build_from_with_errors(path).map(|(tree, _)| tree)
+    }
+
+    /// Scans the directory at `path` like `build_from`, also returning the
+    /// `FileTreeError::ScanTimeout` of each subdirectory left out because reading it
+    /// timed out, see `scan_timeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FileTreeError` naming the first directory or entry that could not be
+    /// read, or the first invalid exclude or include pattern.
+    pub fn build_from_with_errors(
+        &self,
+        path: impl Into<String>,
+    ) -> Result<(FileTree, Vec<FileTreeError>), FileTreeError> {
+        let scanner = Scanner::new(self)?;
+        let path = normalize_path(path.into()).display().to_string();
+        let ancestors = scanner.root_ancestors(&path)?;
+
+        let scan = || scanner.scan_directory(path, self.max_depth, ancestors, Vec::new());
+        #[cfg(feature = "parallel")]
+        let tree = match self.threads {
+            0 => scan(),
+            threads => match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => pool.install(scan),
+                Err(error) => {
+                    log::warn!("Cannot start {} scan threads: {}", threads, error);
+                    scan()
+                }
+            },
+        }?;
+        #[cfg(not(feature = "parallel"))]
+        let tree = scan()?;
+        Ok((tree, scanner.timeouts.into_inner().unwrap()))
+    }
+
+    /// Scans each directory of `paths` with this config, see `build_from`. The trees
+    /// are the `directories` of a synthetic root whose path is `MULTI_ROOT_PATH`,
+    /// in the order of `paths`; use `FileTree::merge` to combine them instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first directory that could not be scanned.
+    pub fn build_from_all<I, S>(&self, paths: I) -> Result<FileTree, FileTreeError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut root = FileTree::new(MULTI_ROOT_PATH.to_string());
+        for path in paths {
+            root.directories.push(self.build_from(path)?);
+        }
+        Ok(root)
+    }
+}
+
+/// Walks the directory at `root` with `config`, without building a `FileTree`.
+/// The kept directories are listed too, each one followed by its content, after
+/// the files of its parent. `root` itself is not listed.
+///
+/// ```no_run
+/// use file_tree::{walk, ScanConfig};
+/// use std::path::Path;
+///
+/// let config = ScanConfig::new().extensions([".mkv"]);
+/// for (path, metadata) in walk(Path::new("/media/Show"), &config).unwrap() {
+///     if metadata.is_file() {
+///         println!("{} ({} bytes)", path.display(), metadata.len());
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns a `FileTreeError` naming the first directory or entry that could not be
+/// read, or the first invalid exclude or include pattern.
+pub fn walk(
+    root: &Path,
+    config: &ScanConfig,
+) -> Result<Vec<(PathBuf, fs::Metadata)>, FileTreeError> {
+    let scanner = Scanner::new(config)?;
+    let root = normalize_path(root).display().to_string();
+    let ancestors = scanner.root_ancestors(&root)?;
+    let mut entries = Vec::new();
+    scanner.walk_directory(&root, config.max_depth, ancestors, Vec::new(), &mut entries)?;
+    Ok(entries)
+}
+
+// A timeout given in seconds, e.g. `30` or `2.5`
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let seconds = Option::<f64>::deserialize(deserializer)?;
+    seconds
+        .map(|seconds| Duration::try_from_secs_f64(seconds).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+// Extensions are matched against lowercased file names
+fn lowercase<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let extensions = Vec::<String>::deserialize(deserializer)?;
+    Ok(extensions
+        .into_iter()
+        .map(|extension| extension.to_lowercase())
+        .collect())
+}
+
+/// A `ScanConfig` with its patterns compiled
+struct Scanner<'a> {
+    config: &'a ScanConfig,
+    exclude: GlobSet,
+    include: GlobSet,
+    /// The subdirectories left out because reading them timed out
+    timeouts: Mutex<Vec<FileTreeError>>,
+    /// Files kept so far, checked against `ScanConfig::max_files`
+    files: AtomicUsize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(config: &'a ScanConfig) -> Result<Self, FileTreeError> {
+        Ok(Scanner {
+            config,
+            exclude: build_glob_set(&config.exclude)?,
+            include: build_glob_set(&config.include)?,
+            timeouts: Mutex::new(Vec::new()),
+            files: AtomicUsize::new(0),
+        })
+    }
+
+    /// The ancestors of the scanned directory at `path`, see `scan_directory`.
+    fn root_ancestors(&self, path: &str) -> Result<Vec<PathBuf>, FileTreeError> {
+        if self.config.follow_symlinks {
+            Ok(vec![canonicalize(path)?])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the ancestors of the subdirectory `dir`, or `None` when following
+    /// it would lead back to one of them.
+    fn descend(
+        &self,
+        dir: &str,
+        ancestors: &[PathBuf],
+    ) -> Result<Option<Vec<PathBuf>>, FileTreeError> {
+        let mut ancestors = ancestors.to_vec();
+        if self.config.follow_symlinks {
+            let canonical = canonicalize(dir)?;
+            if ancestors.contains(&canonical) {
+                log::warn!("Skipping {}, it leads back to {}", dir, canonical.display());
+                return Ok(None);
+            }
+            ancestors.push(canonical);
+        }
+        Ok(Some(ancestors))
+    }
+
+    /// Reads the kept entries of the directory at `path`. The patterns of its
+    /// `.ripignore` are added to `ignores`, the ones of the directories above it.
+    fn read_directory(
+        &self,
+        path: &str,
+        ignores: &mut Vec<Arc<IgnoreFile>>,
+    ) -> Result<DirectoryEntries, FileTreeError> {
+        let follow_symlinks = self.config.follow_symlinks;
+        let mut entries = match self.config.scan_timeout {
+            Some(timeout) => read_entries_within(path, follow_symlinks, timeout)?,
+            None => read_entries(path, follow_symlinks)?,
+        };
+        if let Some(ignore) = IgnoreFile::read(path) {
+            ignores.push(Arc::new(ignore));
+        }
+        let ignored = |entry: &str| ignores.iter().any(|ignore| ignore.is_match(entry));
+        entries
+            .files
+            .retain(|(file, _)| self.keep_file(file) && !ignored(file));
+        entries
+            .directories
+            .retain(|(dir, _)| self.keep(dir) && !ignored(dir));
+
+        if let Some(limit) = self.config.max_files {
+            let count = entries.files.len();
+            if self.files.fetch_add(count, Ordering::Relaxed) + count > limit {
+                return Err(FileTreeError::TooManyFiles {
+                    limit,
+                    path: path.to_string(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn keep(&self, entry: &str) -> bool {
+        !self.exclude.is_match(entry) && (self.config.include_hidden || !is_hidden_or_junk(entry))
+    }
+
+    fn keep_file(&self, file: &str) -> bool {
+        self.keep(file)
+            && (self.include.is_empty() || self.include.is_match(file))
+            && self.has_extension(file)
+    }
+
+    fn has_extension(&self, file: &str) -> bool {
+        let file = file.to_lowercase();
+        self.config.extensions.is_empty()
+            || self
+                .config
+                .extensions
+                .iter()
+                .any(|extension| file.ends_with(extension.as_str()))
+    }
+
+    /// Scans the directory at `path`. When following symbolic links, `ancestors`
+    /// holds the canonical paths of the directories leading to it (itself included).
+    /// `ignores` holds the `.ripignore` files of the directories above it.
+    fn scan_directory(
+        &self,
+        path: String,
+        max_depth: Option<usize>,
+        ancestors: Vec<PathBuf>,
+        mut ignores: Vec<Arc<IgnoreFile>>,
+    ) -> Result<FileTree, FileTreeError> {
+        let entries = self.read_directory(&path, &mut ignores)?;
+        let (files, sizes) = entries
+            .files
+            .into_iter()
+            .map(|(file, metadata)| (file, metadata.len()))
+            .unzip();
+        let mut dirs: Vec<String> = entries
+            .directories
+            .into_iter()
+            .map(|(dir, _)| dir)
+            .collect();
+
+        if max_depth == Some(0) {
+            dirs.clear();
+        }
+        // Subdirectories are sorted so the tree is the same however they are scanned,
+        // rayon collects them in order
+        dirs.sort_by(|a, b| natural_cmp(a, b));
+
+        let scan = |dir: String| match self.descend(&dir, &ancestors)? {
+            Some(ancestors) => {
+                let max_depth = max_depth.map(|depth| depth - 1);
+                match self.scan_directory(dir, max_depth, ancestors, ignores.clone()) {
+                    Err(error @ FileTreeError::ScanTimeout { .. }) => {
+                        log::warn!("Leaving out the directory, {}", error);
+                        self.timeouts.lock().unwrap().push(error);
+                        Ok(None)
+                    }
+                    result => result.map(Some),
+                }
+            }
+            None => Ok(None),
+        };
+        #[cfg(feature = "parallel")]
+        let directories = dirs
+            .into_par_iter()
+            .map(scan)
+            .filter_map(Result::transpose)
+            .collect::<Result<_, _>>()?;
+        #[cfg(not(feature = "parallel"))]
+        let directories = dirs
+            .into_iter()
+            .map(scan)
+            .filter_map(Result::transpose)
+            .collect::<Result<_, _>>()?;
+
+        Ok(FileTree {
+            path,
+            files,
+            sizes,
+            directories,
+        })
+    }
+
+    /// Appends the kept entries below `path` to `walked`, in the order of `walk`.
+    fn walk_directory(
+        &self,
+        path: &str,
+        max_depth: Option<usize>,
+        ancestors: Vec<PathBuf>,
+        mut ignores: Vec<Arc<IgnoreFile>>,
+        walked: &mut Vec<(PathBuf, fs::Metadata)>,
+    ) -> Result<(), FileTreeError> {
+        let entries = self.read_directory(path, &mut ignores)?;
+        walked.extend(
+            entries
+                .files
+                .into_iter()
+                .map(|(file, metadata)| (PathBuf::from(file), metadata)),
+        );
+        if max_depth == Some(0) {
+            return Ok(());
+        }
+        for (dir, metadata) in entries.directories {
+            if let Some(ancestors) = self.descend(&dir, &ancestors)? {
+                walked.push((PathBuf::from(&dir), metadata));
+                let max_depth = max_depth.map(|depth| depth - 1);
+                self.walk_directory(&dir, max_depth, ancestors, ignores.clone(), walked)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the entries of the directory at `path`, see `FileTree::read_entries`.
+fn read_entries(path: &str, follow_symlinks: bool) -> Result<DirectoryEntries, FileTreeError> {
+    let entries = {
+        let _permit = io_limit::acquire();
+        fs::read_dir(path)
+    }
+    .map_err(|error| FileTreeError::io(path, error))?;
+    FileTree::read_entries(entries, follow_symlinks)
+}
+
+// Directories whose timed reads are held up, to time them out in tests
+#[cfg(test)]
+static SLOW_DIRECTORIES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Reads the entries of the directory at `path` on a worker thread, failing with
+/// `FileTreeError::ScanTimeout` when it takes longer than `timeout`. The thread
+/// cannot be interrupted: a read blocked for good keeps it until the process exits.
+/// The I/O permit of the read is held by the calling thread, so that it is
+/// released on timeout rather than leaked with the worker.
+fn read_entries_within(
+    path: &str,
+    follow_symlinks: bool,
+    timeout: Duration,
+) -> Result<DirectoryEntries, FileTreeError> {
+    let _permit = io_limit::acquire();
+    let (sender, receiver) = mpsc::channel();
+    let owned_path = path.to_string();
+    thread::Builder::new()
+        .name("rip-read-dir".to_string())
+        .spawn(move || {
+            io_limit::exempt_current_thread();
+            #[cfg(test)]
+            if SLOW_DIRECTORIES.lock().unwrap().contains(&owned_path) {
+                thread::sleep(Duration::from_secs(2));
+            }
+            // The scan may have given up on the result already
+            let _ = sender.send(read_entries(&owned_path, follow_symlinks));
+        })
+        .map_err(|error| FileTreeError::io(path, error))?;
+    match receiver.recv_timeout(timeout) {
+        Ok(entries) => entries,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(FileTreeError::ScanTimeout {
+            path: path.to_string(),
+            timeout,
+        }),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(FileTreeError::io(
+            path,
+            io::Error::other("the thread reading the directory stopped"),
+        )),
+    }
+}
+
+/// Resolves `path` to an absolute path without symbolic links.
+fn canonicalize(path: &str) -> Result<PathBuf, FileTreeError> {
+    let _permit = io_limit::acquire();
+    fs::canonicalize(path).map_err(|error| FileTreeError::io(path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_timing_out_is_left_out() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["fast", "slow"] {
+            fs::create_dir(root.path().join(dir)).unwrap();
+            fs::write(root.path().join(dir).join("a.mp4"), "").unwrap();
+        }
+        let slow = root.path().join("slow").display().to_string();
+        SLOW_DIRECTORIES.lock().unwrap().push(slow.clone());
+
+        let (tree, errors) = ScanConfig::new()
+            .scan_timeout(Duration::from_millis(200))
+            .build_from_with_errors(root.path().display().to_string())
+            .unwrap();
+
+        let directories: Vec<String> = tree.directories.iter().map(FileTree::name).collect();
+        assert_eq!(directories, ["fast"]);
+        assert!(matches!(
+            &errors[..],
+            [FileTreeError::ScanTimeout { path, .. }] if *path == slow
+        ));
+    }
+}
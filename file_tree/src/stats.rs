@@ -0,0 +1,87 @@
+use crate::{extension_of, human_size, FileTree};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Totals of a tree, as returned by `FileTree::stats`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TreeStats {
+    /// Files of the whole tree
+    pub files: usize,
+    /// Directories below the root
+    pub directories: usize,
+    /// Sum of the known file sizes, `None` when the tree tracks no sizes
+    pub bytes: Option<u64>,
+    /// Number of files per lowercased extension (`.mp4`), `""` for files without one
+    pub extensions: BTreeMap<String, usize>,
+}
+
+impl FileTree {
+    /// Counts the files, directories and bytes of the whole tree.
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        self.collect_stats(&mut stats);
+        stats
+    }
+
+    /// Number of files of the whole tree, like `stats().files` without the rest.
+    pub fn count_files(&self) -> usize {
+        self.files.len()
+            + self
+                .directories
+                .iter()
+                .map(FileTree::count_files)
+                .sum::<usize>()
+    }
+
+    /// Number of directory levels below the root: `0` for a tree without
+    /// subdirectories, `1` when they hold no directory, and so on.
+    pub fn depth(&self) -> usize {
+        self.directories
+            .iter()
+            .map(|directory| directory.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn collect_stats(&self, stats: &mut TreeStats) {
+        stats.files += self.files.len();
+        stats.directories += self.directories.len();
+        if !self.sizes.is_empty() {
+            *stats.bytes.get_or_insert(0) += self.sizes.iter().sum::<u64>();
+        }
+        for file in &self.files {
+            let extension = extension_of(file).to_lowercase();
+            *stats.extensions.entry(extension).or_insert(0) += 1;
+        }
+
+        for directory in &self.directories {
+            directory.collect_stats(stats);
+        }
+    }
+}
+
+/// Formats the stats as a one-line summary, e.g.
+/// `3412 files (3400 .mp4, 12 .srt) across 57 directories totaling 1.8 TiB`.
+impl fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} files", self.files)?;
+        if !self.extensions.is_empty() {
+            let mut extensions: Vec<(&String, &usize)> = self.extensions.iter().collect();
+            extensions.sort_by(|a, b| b.1.cmp(a.1));
+            let extensions: Vec<String> = extensions
+                .into_iter()
+                .map(|(extension, count)| match extension.as_str() {
+                    "" => format!("{} without extension", count),
+                    extension => format!("{} {}", count, extension),
+                })
+                .collect();
+            write!(f, " ({})", extensions.join(", "))?;
+        }
+        write!(f, " across {} directories", self.directories)?;
+        if let Some(bytes) = self.bytes {
+            write!(f, " totaling {}", human_size(bytes))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{normalize_path, FileTree, FileTreeError};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+impl FileTree {
+    /// Builds a FileTree from a list of paths without touching the filesystem.
+    ///
+    /// An entry is a directory when another entry lies beneath it (or when it ends
+    /// with a separator); every other entry is a file. The root of the tree is the
+    /// deepest directory containing every entry. Entries are compared component by
+    /// component, so `Season 1` never swallows the content of `Season 10`. When
+    /// absolute and relative paths are mixed, the relative ones are resolved against
+    /// the current directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The paths of the files and directories of the tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FileTreeError::EmptyInput` when `values` is empty, and
+    /// `FileTreeError::EmptyPath` when one of them is.
+    pub fn from_string_vector(values: Vec<String>) -> Result<Self, FileTreeError> {
+        if values.is_empty() {
+            return Err(FileTreeError::EmptyInput);
+        }
+        if let Some(index) = values.iter().position(String::is_empty) {
+            return Err(FileTreeError::EmptyPath { index });
+        }
+
+        let is_absolute = |value: &String| Path::new(value).is_absolute();
+        let values: Vec<String> = if values.iter().any(is_absolute) {
+            values
+                .into_iter()
+                .map(|value| match std::path::absolute(&value) {
+                    Ok(path) if !is_absolute(&value) => path.display().to_string(),
+                    _ => value,
+                })
+                .collect()
+        } else {
+            values
+        };
+
+        let entries: Vec<(&String, Vec<Component>)> = values
+            .iter()
+            .map(|value| (value, Path::new(value).components().collect()))
+            .collect();
+
+        let ancestors: HashSet<&[Component]> = entries
+            .iter()
+            .flat_map(|(_, components)| (1..components.len()).map(|len| &components[..len]))
+            .collect();
+        let directories: HashSet<&[Component]> = entries
+            .iter()
+            .map(|(value, components)| (value, components.as_slice()))
+            .filter(|(value, components)| value.ends_with('/') || ancestors.contains(components))
+            .map(|(_, components)| components)
+            .collect();
+
+        let mut root: &[Component] = &entries[0].1;
+        for (_, components) in &entries[1..] {
+            let common = root
+                .iter()
+                .zip(components)
+                .take_while(|(a, b)| a == b)
+                .count();
+            root = &root[..common];
+        }
+        // A lone file is its own common prefix, its directory is the root
+        let root_is_file = entries
+            .iter()
+            .any(|(_, components)| components.as_slice() == root && !directories.contains(root));
+        if root_is_file {
+            root = &root[..root.len() - 1];
+        }
+
+        let root_path: PathBuf = root.iter().collect();
+        let mut tree = Self::new(normalize_path(root_path).display().to_string());
+        for (value, components) in &entries {
+            let relative = &components[root.len()..];
+            if directories.contains(components.as_slice()) {
+                tree.insert_directory(relative);
+            } else if let Some((_, parents)) = relative.split_last() {
+                tree.insert_directory(parents)
+                    .files
+                    .push(normalize_path(value).display().to_string());
+            }
+        }
+
+        Ok(tree)
+    }
+
+    /// Returns the directory at `relative` below this tree, creating the missing ones.
+    fn insert_directory(&mut self, relative: &[Component]) -> &mut FileTree {
+        let Some((name, rest)) = relative.split_first() else {
+            return self;
+        };
+
+        let path = Path::new(&self.path).join(name).display().to_string();
+        let index = match self.directories.iter().position(|dir| dir.path == path) {
+            Some(index) => index,
+            None => {
+                self.directories.push(Self::new(path));
+                self.directories.len() - 1
+            }
+        };
+        self.directories[index].insert_directory(rest)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{FileTree, FileTreeError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default `LinkOptions::group_template` of `GroupingType::Plex`
+pub const PLEX_GROUP_TEMPLATE: &str = "Season {index:02}";
+
+/// A piece of a template split by `tokenize`
+#[derive(Debug)]
+enum Piece<'a> {
+    /// Literal text, with `{{` and `}}` unescaped
+    Text(String),
+    /// The content of a `{...}` placeholder
+    Placeholder(&'a str),
+}
+
+/// A piece of a parsed group template
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    /// The season number, zero-padded to `width` digits
+    Index {
+        width: usize,
+    },
+    Name,
+    Type,
+}
+
+/// A season directory naming template, e.g. `Season {index:02}`. The placeholders
+/// are `{index}` (the season number, `{index:02}` zero-padding it to two digits),
+/// `{name}` (the name of the group) and `{type}` (`Season` in Plex mode, `Group`
+/// otherwise). `{{` and `}}` stand for literal braces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GroupTemplate {
+    segments: Vec<Segment>,
+}
+
+impl GroupTemplate {
+    /// Parses `template`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FileTreeError::InvalidTemplate` for an unknown placeholder, an invalid
+    /// `{index}` width or an unbalanced brace.
+    pub(crate) fn parse(template: &str) -> Result<Self, FileTreeError> {
+        let segments = tokenize(template)?
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => Ok(Segment::Text(text)),
+                Piece::Placeholder("index") => Ok(Segment::Index { width: 0 }),
+                Piece::Placeholder("name") => Ok(Segment::Name),
+                Piece::Placeholder("type") => Ok(Segment::Type),
+                Piece::Placeholder(placeholder) => match placeholder.split_once(':') {
+                    Some(("index", width)) if width.starts_with('0') => width
+                        .parse()
+                        .map(|width| Segment::Index { width })
+                        .map_err(|_| {
+                            invalid(template, format!("invalid width `{}` of {{index}}", width))
+                        }),
+                    _ => Err(unknown_placeholder(template, placeholder)),
+                },
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { segments })
+    }
+
+    /// Renders the template for the season `index` of the group `name`.
+    pub(crate) fn render(&self, index: u32, name: &str, kind: &str) -> String {
+        let mut output = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Index { width } => output.push_str(&format!("{:0width$}", index)),
+                Segment::Name => output.push_str(name),
+                Segment::Type => output.push_str(kind),
+            }
+        }
+        output
+    }
+}
+
+/// Expands the placeholders of a destination `template`, e.g. `{source}/../_plex/{show}`
+/// to link several shows with one command. The placeholders are `{source}` (the
+/// directory being linked, as given), `{show}` (its name, see `FileTree::name`) and
+/// `{date}` (today's date in UTC, as `YYYY-MM-DD`). `{{` and `}}` stand for literal
+/// braces; a destination without placeholders is returned as is.
+///
+/// # Errors
+///
+/// Returns `FileTreeError::InvalidTemplate` for an unknown placeholder or an
+/// unbalanced brace.
+pub fn expand_destination(template: &str, source: &str) -> Result<String, FileTreeError> {
+    let mut destination = String::new();
+    for piece in tokenize(template)? {
+        match piece {
+            Piece::Text(text) => destination.push_str(&text),
+            Piece::Placeholder("source") => destination.push_str(source),
+            Piece::Placeholder("show") => {
+                destination.push_str(&FileTree::new(source.to_string()).name())
+            }
+            Piece::Placeholder("date") => destination.push_str(&today()),
+            Piece::Placeholder(placeholder) => {
+                return Err(unknown_placeholder(template, placeholder))
+            }
+        }
+    }
+    Ok(destination)
+}
+
+// Splits `template` into literal text and `{...}` placeholders
+fn tokenize(template: &str) -> Result<Vec<Piece<'_>>, FileTreeError> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' => return Err(invalid(template, "unmatched `}`".to_string())),
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| invalid(template, "unclosed `{`".to_string()))?;
+                chars = rest[end + 1..].chars();
+
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Placeholder(&rest[..end]));
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+fn invalid(template: &str, reason: String) -> FileTreeError {
+    FileTreeError::InvalidTemplate {
+        template: template.to_string(),
+        reason,
+    }
+}
+
+fn unknown_placeholder(template: &str, placeholder: &str) -> FileTreeError {
+    invalid(template, format!("unknown placeholder {{{}}}", placeholder))
+}
+
+// Today's date in UTC as `YYYY-MM-DD`, from the days since the Unix epoch
+// (http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,128 @@
+use crate::FileTree;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// Width of every indentation step and connector of a rendered tree
+const INDENT_PREFIXES: [&str; 4] = ["├── ", "└── ", "│   ", "    "];
+
+// `File 3: `, `Directory 12: `
+fn index_prefix_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^(File|Directory) \d+: ").unwrap())
+}
+
+/// An entry of a rendered tree: its depth below the root, its full path and
+/// whether its label marks it as a directory
+struct TreeLine {
+    depth: usize,
+    path: String,
+    marked_directory: bool,
+}
+
+impl FileTree {
+    /// Builds a FileTree from a tree rendered by `to_file_tree`, so a printed tree
+    /// can be parsed back. The box-drawing connectors and `File N:` / `Directory N:`
+    /// prefixes are stripped and the nesting is recovered from the indentation.
+    /// Entries may be full paths or bare names, which are joined to their parent.
+    ///
+    /// Files and directories are told apart from the tree alone, without touching
+    /// the filesystem, so trees describing another machine can be parsed. An entry
+    /// is a directory when it has nested entries, ends with a separator, carries a
+    /// `Directory N:` prefix or follows a directory among its siblings (trees list
+    /// files first). Every other entry is a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The rendered tree, its first line being the root.
+    pub fn from_file_tree(contents: String) -> Self {
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+        let Some(root) = lines.next() else {
+            return Self::new(String::new());
+        };
+        let (root, _) = strip_label(root.trim());
+        let root = root.to_string();
+
+        let mut parents = vec![root.clone()];
+        let mut entries = Vec::new();
+        for line in lines {
+            let (depth, label) = split_indentation(line);
+            let depth = depth.clamp(1, parents.len());
+            parents.truncate(depth);
+
+            let parent = &parents[depth - 1];
+            let (label, marked_directory) = strip_label(label.trim_end());
+            let path = if Path::new(label).parent() == Some(Path::new(parent)) {
+                label.to_string()
+            } else {
+                Path::new(parent).join(label).display().to_string()
+            };
+            parents.push(path.clone());
+            entries.push(TreeLine {
+                depth,
+                path,
+                marked_directory,
+            });
+        }
+
+        // Whether a directory was already listed among the siblings at each depth
+        let mut directory_listed = vec![false];
+
+        let mut stack = vec![Self::new(root)];
+        for (index, entry) in entries.iter().enumerate() {
+            while stack.len() > entry.depth {
+                let directory = stack.pop().unwrap();
+                stack.last_mut().unwrap().directories.push(directory);
+            }
+
+            let has_children = entries
+                .get(index + 1)
+                .is_some_and(|next| next.depth > entry.depth);
+            directory_listed.resize(entry.depth + 1, false);
+            directory_listed[entry.depth] |= has_children || entry.marked_directory;
+            if directory_listed[entry.depth] {
+                stack.push(Self::new(entry.path.clone()));
+            } else {
+                stack.last_mut().unwrap().files.push(entry.path.clone());
+            }
+        }
+        while stack.len() > 1 {
+            let directory = stack.pop().unwrap();
+            stack.last_mut().unwrap().directories.push(directory);
+        }
+
+        stack.pop().unwrap()
+    }
+}
+
+/// Splits a rendered line into its depth (the number of indentation steps,
+/// connector included) and its label.
+fn split_indentation(line: &str) -> (usize, &str) {
+    let mut depth = 0;
+    let mut rest = line;
+    while let Some(stripped) = INDENT_PREFIXES
+        .iter()
+        .find_map(|prefix| rest.strip_prefix(prefix))
+    {
+        depth += 1;
+        rest = stripped;
+    }
+    (depth, rest)
+}
+
+/// Strips the index prefix and trailing separator of a label, telling whether
+/// either marked it as a directory.
+fn strip_label(label: &str) -> (&str, bool) {
+    let (label, marked_directory) = match index_prefix_regex().captures(label) {
+        Some(captures) => (
+            &label[captures.get(0).unwrap().end()..],
+            &captures[1] == "Directory",
+        ),
+        None => (label, false),
+    };
+    let is_separator = |c: char| matches!(c, '/' | '\\');
+    match label.trim_end_matches(is_separator) {
+        trimmed if trimmed.len() < label.len() && !trimmed.is_empty() => (trimmed, true),
+        _ => (label, marked_directory),
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "tokio")]
+
+#[cfg(test)]
+mod tests {
+    use file_tree::{FileTree, FileTreeError, IGNORE_FILE_NAME};
+    use std::fs;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_from_directory_async_matches_the_sync_scan() {
+        let root = tempfile::tempdir().unwrap();
+        for file in [
+            "a.mp4",
+            "Season 1/b.mp4",
+            "Season 1/Extras/c.mp4",
+            "Season 2/d.mkv",
+        ] {
+            let path = root.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "abc").unwrap();
+        }
+        fs::write(root.path().join(".DS_Store"), "").unwrap();
+
+        let path = root.path().display().to_string();
+        let tree = block_on(FileTree::from_directory_async(path.clone())).unwrap();
+        let expected = FileTree::try_from_directory(path, None, &[], false).unwrap();
+
+        assert!(tree.structurally_eq(&expected));
+        assert_eq!(tree.stats(), expected.stats());
+    }
+
+    #[test]
+    fn test_from_directory_async_honors_ripignore() {
+        let root = tempfile::tempdir().unwrap();
+        for file in [
+            "keep.mp4",
+            "skip.mp4",
+            "Season 1/a.mp4",
+            "Season 1/Extras/b.mp4",
+        ] {
+            let path = root.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(root.path().join(IGNORE_FILE_NAME), "skip.mp4\n").unwrap();
+        fs::write(
+            root.path().join("Season 1").join(IGNORE_FILE_NAME),
+            "Extras\n",
+        )
+        .unwrap();
+
+        let path = root.path().display().to_string();
+        let tree = block_on(FileTree::from_directory_async(path.clone())).unwrap();
+        let expected = FileTree::try_from_directory(path, None, &[], false).unwrap();
+
+        assert!(tree.structurally_eq(&expected));
+        assert_eq!(tree.count_files(), 2);
+    }
+
+    #[test]
+    fn test_from_directory_async_reports_a_missing_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let missing = root.path().join("missing").display().to_string();
+
+        let result = block_on(FileTree::from_directory_async(missing.clone()));
+
+        assert!(matches!(result, Err(FileTreeError::Io { path, .. }) if path == missing));
+    }
+}
@@ -0,0 +1,82 @@
+#![cfg(unix)]
+
+#[cfg(test)]
+mod tests {
+    use file_tree::{clean_links, CleanSummary};
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_clean_links_keeps_regular_files() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let season = destination.path().join("Season 01");
+        fs::create_dir(&season).unwrap();
+        fs::write(season.join("notes.txt"), "").unwrap();
+        symlink(source.path().join("a.mp4"), season.join("S01E01 - a.mp4")).unwrap();
+
+        let summary = clean_links(destination.path(), false).unwrap();
+        assert_eq!(
+            summary,
+            CleanSummary {
+                links: 1,
+                directories: 0
+            }
+        );
+        assert!(season.join("notes.txt").is_file());
+        assert!(fs::symlink_metadata(season.join("S01E01 - a.mp4")).is_err());
+    }
+
+    #[test]
+    fn test_clean_links_dangling_only() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let live = destination.path().join("S01E01 - a.mp4");
+        let dangling = destination.path().join("S01E02 - b.mp4");
+        symlink(source.path().join("a.mp4"), &live).unwrap();
+        symlink(source.path().join("b.mp4"), &dangling).unwrap();
+
+        let summary = clean_links(destination.path(), true).unwrap();
+        assert_eq!(summary.links, 1);
+        assert!(fs::symlink_metadata(&live).is_ok());
+        assert!(fs::symlink_metadata(&dangling).is_err());
+    }
+
+    #[test]
+    fn test_clean_links_prunes_emptied_directories() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let nested = destination.path().join("Show").join("Season 01");
+        fs::create_dir_all(&nested).unwrap();
+        symlink(source.path().join("a.mp4"), nested.join("S01E01 - a.mp4")).unwrap();
+        fs::create_dir(destination.path().join("Empty")).unwrap();
+
+        let summary = clean_links(destination.path(), false).unwrap();
+        assert_eq!(
+            summary,
+            CleanSummary {
+                links: 1,
+                directories: 3
+            }
+        );
+        assert!(destination.path().is_dir());
+        assert_eq!(fs::read_dir(destination.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_clean_links_removes_links_to_directories() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let link = destination.path().join("Season 01");
+        symlink(source.path(), &link).unwrap();
+
+        let summary = clean_links(destination.path(), false).unwrap();
+        assert_eq!(summary.links, 1);
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert!(source.path().join("a.mp4").is_file());
+    }
+}
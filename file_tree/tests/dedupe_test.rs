@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use file_tree::{FileTree, LinkOptions};
+    use std::fs;
+
+    #[test]
+    fn test_find_duplicates_groups_identical_files() {
+        let source = tempfile::tempdir().unwrap();
+        fs::create_dir(source.path().join("Copy")).unwrap();
+        let large: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut changed = large.clone();
+        changed[3 * 1024 * 1024 - 1] ^= 1;
+        fs::write(source.path().join("Episode 2.mp4"), &large).unwrap();
+        fs::write(source.path().join("Episode 10.mp4"), &large).unwrap();
+        fs::write(source.path().join("Copy").join("Episode 2.mp4"), &large).unwrap();
+        fs::write(source.path().join("Changed.mp4"), &changed).unwrap();
+        fs::write(source.path().join("Other.mp4"), "other").unwrap();
+
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let duplicates = tree.find_duplicates();
+
+        let root = source.path().display().to_string();
+        assert_eq!(
+            duplicates,
+            [[
+                format!("{}/Copy/Episode 2.mp4", root),
+                format!("{}/Episode 2.mp4", root),
+                format!("{}/Episode 10.mp4", root),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_links_only_the_first_duplicate() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "pilot").unwrap();
+        fs::write(source.path().join("b.mp4"), "pilot").unwrap();
+        fs::write(source.path().join("c.mp4"), "finale").unwrap();
+
+        let options = LinkOptions {
+            dedupe: true,
+            ..Default::default()
+        };
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let summary = tree
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        assert_eq!(summary.created.len(), 2);
+        assert!(destination.path().join("S01E01 - a.mp4").exists());
+        assert!(destination.path().join("S01E02 - c.mp4").exists());
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use file_tree::{is_special, parse_season_episode, split_part, DEFAULT_SPECIALS_KEYWORDS};
+
+    #[test]
+    fn test_parse_season_episode_patterns() {
+        assert_eq!(parse_season_episode("Show.S01E02.720p.mkv"), Some((1, 2)));
+        assert_eq!(parse_season_episode("show s3e14"), Some((3, 14)));
+        assert_eq!(parse_season_episode("Show 1x02 - Title.mp4"), Some((1, 2)));
+        assert_eq!(parse_season_episode("Episode 7.mp4"), Some((1, 7)));
+    }
+
+    #[test]
+    fn test_parse_season_episode_ignores_unrelated_numbers() {
+        assert_eq!(parse_season_episode("Movie 1920x1080.mp4"), None);
+        assert_eq!(parse_season_episode("Lecture 12.mp4"), None);
+    }
+
+    #[test]
+    fn test_split_part() {
+        assert_eq!(
+            split_part("Episode - part1.mkv"),
+            Some(("Episode - .mkv".to_string(), 1))
+        );
+        assert_eq!(
+            split_part("Movie pt.2.avi"),
+            Some(("Movie .avi".to_string(), 2))
+        );
+        assert_eq!(
+            split_part("Film CD1.mkv"),
+            Some(("Film .mkv".to_string(), 1))
+        );
+        assert_eq!(split_part("Apartment 2.mkv"), None);
+        assert_eq!(split_part("Episode 3.mkv"), None);
+        assert_eq!(split_part("Part 3.mkv"), None);
+    }
+
+    #[test]
+    fn test_is_special() {
+        let keywords = DEFAULT_SPECIALS_KEYWORDS.map(String::from);
+        assert!(is_special("Show - OVA 2.mkv", &keywords));
+        assert!(is_special("Specials", &keywords));
+        assert!(is_special("Christmas.Special.mkv", &keywords));
+        assert!(!is_special("Extraordinary.mkv", &keywords));
+        assert!(!is_special("Episode 3.mkv", &keywords));
+        assert!(!is_special("Show - OVA 2.mkv", &[]));
+    }
+}
@@ -0,0 +1,620 @@
+#[cfg(test)]
+mod tests {
+    use file_tree::{diff, normalize_path, FileTree, FileTreeError, ScanConfig, MULTI_ROOT_PATH};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_name_of_root() {
+        assert_eq!(FileTree::new("/".to_string()).name(), "/");
+    }
+
+    #[test]
+    fn test_name_ignores_trailing_separator() {
+        assert_eq!(FileTree::new("/home/user/".to_string()).name(), "user");
+    }
+
+    #[test]
+    fn test_name_of_windows_path() {
+        assert_eq!(FileTree::new("C:\\Media\\Show".to_string()).name(), "Show");
+    }
+
+    #[test]
+    fn test_from_string_vector_rejects_empty_input() {
+        assert!(matches!(
+            FileTree::from_string_vector(Vec::new()),
+            Err(FileTreeError::EmptyInput)
+        ));
+    }
+
+    #[test]
+    fn test_from_string_vector_rejects_empty_paths() {
+        assert!(matches!(
+            FileTree::from_string_vector(vec!["a/b.mp4".to_string(), String::new()]),
+            Err(FileTreeError::EmptyPath { index: 1 })
+        ));
+        assert!(matches!(
+            FileTree::from_string_vector(vec![String::new()]),
+            Err(FileTreeError::EmptyPath { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_from_string_vector_keeps_prefixed_siblings_apart() {
+        let values = [
+            "/media/Show",
+            "/media/Show/Season 1",
+            "/media/Show/Season 10",
+            "/media/Show/Season 1/a.mp4",
+            "/media/Show/Season 10/b.mp4",
+        ];
+        let tree =
+            FileTree::from_string_vector(values.iter().map(|v| v.to_string()).collect()).unwrap();
+
+        assert_eq!(tree.path, "/media/Show");
+        assert_eq!(tree.directories.len(), 2);
+        for directory in &tree.directories {
+            let expected = match directory.path.as_str() {
+                "/media/Show/Season 1" => "/media/Show/Season 1/a.mp4",
+                "/media/Show/Season 10" => "/media/Show/Season 10/b.mp4",
+                other => panic!("unexpected directory {}", other),
+            };
+            assert_eq!(directory.files, [expected]);
+        }
+    }
+
+    #[test]
+    fn test_from_string_vector_resolves_mixed_relative_paths() {
+        let current = std::env::current_dir().unwrap();
+        let relative = "Show/a.mp4".to_string();
+        let absolute = current.join("Show/b.mp4").display().to_string();
+
+        let tree = FileTree::from_string_vector(vec![relative, absolute]).unwrap();
+
+        assert_eq!(tree.path, current.join("Show").display().to_string());
+        assert_eq!(tree.files.len(), 2);
+    }
+
+    // The input paths of a case and the (directory, file) pairs expected from them
+    type GroupingCase = (
+        &'static [&'static str],
+        &'static [(&'static str, &'static str)],
+    );
+
+    // Pairs every file of the tree with the directory holding it
+    fn files_by_directory(tree: &FileTree) -> Vec<(String, String)> {
+        let mut pairs: Vec<(String, String)> = tree
+            .files
+            .iter()
+            .map(|file| (tree.path.clone(), file.clone()))
+            .collect();
+        for directory in &tree.directories {
+            pairs.extend(files_by_directory(directory));
+        }
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_from_string_vector_places_files_in_their_directory() {
+        let cases: [GroupingCase; 4] = [
+            (
+                &["root", "root/A", "root/AB", "root/A/x.mp4", "root/AB/y.mp4"],
+                &[("root/A", "root/A/x.mp4"), ("root/AB", "root/AB/y.mp4")],
+            ),
+            (
+                &["root", "root/Show", "root/Show.mkv", "root/Show/ep.mkv"],
+                &[("root", "root/Show.mkv"), ("root/Show", "root/Show/ep.mkv")],
+            ),
+            (
+                &[
+                    "root/Season 1/a.mp4",
+                    "root/Season 10/b.mp4",
+                    "root/Season 1 Extras/c.mp4",
+                ],
+                &[
+                    ("root/Season 1", "root/Season 1/a.mp4"),
+                    ("root/Season 1 Extras", "root/Season 1 Extras/c.mp4"),
+                    ("root/Season 10", "root/Season 10/b.mp4"),
+                ],
+            ),
+            (
+                &["root/A/B/x.mp4", "root/A/BC/y.mp4", "root/AB/z.mp4"],
+                &[
+                    ("root/A/B", "root/A/B/x.mp4"),
+                    ("root/A/BC", "root/A/BC/y.mp4"),
+                    ("root/AB", "root/AB/z.mp4"),
+                ],
+            ),
+        ];
+
+        for (values, expected) in cases {
+            let tree = FileTree::from_string_vector(values.iter().map(|v| v.to_string()).collect())
+                .unwrap();
+            let expected: Vec<(String, String)> = expected
+                .iter()
+                .map(|(directory, file)| (directory.to_string(), file.to_string()))
+                .collect();
+            assert_eq!(files_by_directory(&tree), expected, "for {:?}", values);
+        }
+    }
+
+    #[test]
+    fn test_from_string_vector_keeps_root_files_at_the_root() {
+        let values = [
+            "/media/Show",
+            "/media/Show/S",
+            "/media/Show/Specials and Extras",
+            "/media/Show/b.mkv",
+            "/media/Show/A Very Long Pilot Episode Name.mkv",
+            "/media/Show/S/x.mkv",
+            "/media/Show/Specials and Extras/y.mkv",
+        ];
+        let tree =
+            FileTree::from_string_vector(values.iter().map(|v| v.to_string()).collect()).unwrap();
+
+        assert_eq!(tree.path, "/media/Show");
+        assert_eq!(
+            tree.files,
+            [
+                "/media/Show/b.mkv",
+                "/media/Show/A Very Long Pilot Episode Name.mkv"
+            ]
+        );
+        let directories: Vec<&str> = tree.directories.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            directories,
+            ["/media/Show/S", "/media/Show/Specials and Extras"]
+        );
+    }
+
+    #[test]
+    fn test_structurally_eq_ignores_the_order() {
+        let tree = |files: [&str; 3]| {
+            FileTree::from_string_vector(files.iter().map(|file| file.to_string()).collect())
+                .unwrap()
+        };
+        let a = tree(["/m/Show/S1/a.mp4", "/m/Show/S2/b.mp4", "/m/Show/c.mp4"]);
+        let b = tree(["/m/Show/c.mp4", "/m/Show/S2/b.mp4", "/m/Show/S1/a.mp4"]);
+        let c = tree(["/m/Show/S1/a.mp4", "/m/Show/S2/b.mp4", "/m/Show/d.mp4"]);
+
+        assert!(a.structurally_eq(&b));
+        assert!(!a.structurally_eq(&c));
+        assert_eq!(a, FileTree::from_json(&a.to_json().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_depth_and_count_files() {
+        let tree = FileTree::from_string_vector(
+            [
+                "/m/Show/trailer.mp4",
+                "/m/Show/Season 1/e1.mp4",
+                "/m/Show/Season 1/e2.mp4",
+                "/m/Show/Season 2/Extras/x.mp4",
+            ]
+            .iter()
+            .map(|file| file.to_string())
+            .collect(),
+        )
+        .unwrap();
+
+        assert_eq!(tree.count_files(), 4);
+        assert_eq!(tree.depth(), 2);
+        assert_eq!(tree.count_files(), tree.stats().files);
+        assert_eq!(FileTree::new("/m/Empty".to_string()).depth(), 0);
+        assert_eq!(FileTree::new("/m/Empty".to_string()).count_files(), 0);
+    }
+
+    #[test]
+    fn test_merge_unions_directories_with_the_same_name() {
+        let tree = |files: &[&str]| {
+            FileTree::from_string_vector(files.iter().map(|file| file.to_string()).collect())
+                .unwrap()
+        };
+        let a = tree(&["/mnt/a/Show/Season 1/e1.mp4", "/mnt/a/Show/Extras/x.mp4"]);
+        let b = tree(&["/mnt/b/Show/Season 2/e1.mp4", "/mnt/b/Show/Extras/x.mp4"]);
+
+        let merged = a.merge(b);
+
+        assert_eq!(merged.path, "/mnt/a/Show");
+        let directories: Vec<(String, &[String])> = merged
+            .directories
+            .iter()
+            .map(|directory| (directory.name(), directory.files.as_slice()))
+            .collect();
+        assert_eq!(
+            directories,
+            [
+                (
+                    "Season 1".to_string(),
+                    &["/mnt/a/Show/Season 1/e1.mp4".to_string()][..]
+                ),
+                (
+                    "Extras".to_string(),
+                    &[
+                        "/mnt/a/Show/Extras/x.mp4".to_string(),
+                        "/mnt/b/Show/Extras/x.mp4".to_string()
+                    ][..]
+                ),
+                (
+                    "Season 2".to_string(),
+                    &["/mnt/b/Show/Season 2/e1.mp4".to_string()][..]
+                ),
+            ]
+        );
+    }
+
+    fn playlist_tree() -> FileTree {
+        let values = [
+            "/media/Show/Episode 10.mp4",
+            "/media/Show/Episode 2.mp4",
+            "/media/Show/Episode 1.mkv",
+            "/media/Show/Episode 1.srt",
+        ];
+        FileTree::from_string_vector(values.iter().map(|v| v.to_string()).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_to_m3u_lists_episodes_in_natural_order() {
+        let extensions = [".mp4".to_string(), ".mkv".to_string()];
+        assert_eq!(
+            playlist_tree().to_m3u(&extensions, None),
+            "#EXTM3U\n\
+             /media/Show/Episode 1.mkv\n\
+             /media/Show/Episode 2.mp4\n\
+             /media/Show/Episode 10.mp4\n"
+        );
+    }
+
+    #[test]
+    fn test_to_m3u_only_lists_the_given_extensions() {
+        assert_eq!(
+            playlist_tree().to_m3u(&[], None),
+            "#EXTM3U\n/media/Show/Episode 2.mp4\n/media/Show/Episode 10.mp4\n"
+        );
+        assert_eq!(
+            playlist_tree().to_m3u(&[".SRT".to_string()], None),
+            "#EXTM3U\n/media/Show/Episode 1.srt\n"
+        );
+    }
+
+    #[test]
+    fn test_to_m3u_relative_to_the_playlist_directory() {
+        let playlist = playlist_tree().to_m3u(&[], Some(Path::new("/media/Playlists")));
+        assert_eq!(
+            playlist,
+            "#EXTM3U\n../Show/Episode 2.mp4\n../Show/Episode 10.mp4\n"
+        );
+    }
+
+    #[test]
+    fn test_from_directories_keeps_each_root_under_a_synthetic_root() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        fs::write(a.path().join("e1.mp4"), "").unwrap();
+        fs::write(b.path().join("e2.mp4"), "").unwrap();
+        let roots = vec![
+            a.path().display().to_string(),
+            b.path().display().to_string(),
+        ];
+
+        let tree = FileTree::from_directories(roots.clone());
+
+        assert_eq!(tree.path, MULTI_ROOT_PATH);
+        assert!(tree.files.is_empty());
+        let directories: Vec<&str> = tree
+            .directories
+            .iter()
+            .map(|directory| directory.path.as_str())
+            .collect();
+        assert_eq!(directories, roots);
+        assert_eq!(tree.count_files(), 2);
+
+        let missing = a.path().join("missing").display().to_string();
+        assert!(ScanConfig::new()
+            .build_from_all([roots[0].clone(), missing])
+            .is_err());
+    }
+
+    #[test]
+    fn test_prune_empty_drops_branches_without_files() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["Season 1/Extras", "Artwork/Posters", "Empty"] {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        fs::write(root.path().join("Season 1").join("e1.mp4"), "").unwrap();
+        fs::write(
+            root.path().join("Season 1").join("Extras").join("e1.nfo"),
+            "",
+        )
+        .unwrap();
+        fs::write(
+            root.path().join("Artwork").join("Posters").join("a.jpg"),
+            "",
+        )
+        .unwrap();
+
+        let mut tree = ScanConfig::new()
+            .extensions([".mp4"])
+            .build_from(root.path().display().to_string())
+            .unwrap();
+        assert_eq!(tree.stats().directories, 5);
+        tree.prune_empty();
+
+        let directories: Vec<String> = tree
+            .directories
+            .iter()
+            .map(|directory| directory.name())
+            .collect();
+        assert_eq!(directories, ["Season 1"]);
+        assert!(tree.directories[0].directories.is_empty());
+        assert_eq!(tree.count_files(), 1);
+
+        let mut empty = FileTree::new("/empty".to_string());
+        empty.prune_empty();
+        assert_eq!(empty, FileTree::new("/empty".to_string()));
+    }
+
+    #[test]
+    fn test_find_returns_the_matching_paths() {
+        let tree = FileTree::from_string_vector(
+            [
+                "/m/Show/S1/Pilot.mkv",
+                "/m/Show/S1/Pilot.srt",
+                "/m/Show/S2/b.MKV",
+            ]
+            .iter()
+            .map(|file| file.to_string())
+            .collect(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            tree.find(|path| path.ends_with("Pilot.mkv")),
+            ["/m/Show/S1/Pilot.mkv"]
+        );
+        assert_eq!(
+            tree.find_by_extension(".mkv"),
+            ["/m/Show/S1/Pilot.mkv", "/m/Show/S2/b.MKV"]
+        );
+        assert!(tree.find(|_| false).is_empty());
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path("/media//Show/./S1/"),
+            Path::new("/media/Show/S1")
+        );
+        assert_eq!(normalize_path("./Show/a.mp4"), Path::new("Show/a.mp4"));
+        assert_eq!(normalize_path("Show/../Other"), Path::new("Show/../Other"));
+        assert_eq!(normalize_path("./"), Path::new("."));
+    }
+
+    #[test]
+    fn test_from_string_vector_normalizes_the_paths() {
+        let tree = FileTree::from_string_vector(vec![
+            "/media//Show/./S1/a.mp4".to_string(),
+            "/media/Show/S2//b.mp4".to_string(),
+        ])
+        .unwrap();
+
+        assert_eq!(tree.path, "/media/Show");
+        assert_eq!(
+            tree.to_file_list(""),
+            ["/media/Show/S1/a.mp4", "/media/Show/S2/b.mp4"]
+        );
+    }
+
+    #[test]
+    fn test_stats_counts_the_whole_tree() {
+        let mut tree = FileTree::new("/media/Show".to_string());
+        tree.files = vec![
+            "/media/Show/a.mp4".to_string(),
+            "/media/Show/a.srt".to_string(),
+        ];
+        tree.sizes = vec![1024, 24];
+        let mut season = FileTree::new("/media/Show/Season 2".to_string());
+        season.files = vec!["/media/Show/Season 2/b.MP4".to_string()];
+        tree.directories.push(season);
+
+        let stats = tree.stats();
+        assert_eq!((stats.files, stats.directories), (3, 1));
+        assert_eq!(stats.bytes, Some(1048));
+        assert_eq!(stats.extensions[".mp4"], 2);
+        assert_eq!(
+            stats.to_string(),
+            "3 files (2 .mp4, 1 .srt) across 1 directories totaling 1.0 KiB"
+        );
+    }
+
+    #[test]
+    fn test_from_file_tree_round_trips_the_rendered_tree() {
+        let root = tempfile::tempdir().unwrap();
+        let season = root.path().join("Season 1");
+        fs::create_dir_all(season.join("Extras")).unwrap();
+        fs::write(root.path().join("poster.jpg"), "").unwrap();
+        fs::write(season.join("Episode 1.mkv"), "").unwrap();
+        fs::write(season.join("Extras").join("Trailer.mkv"), "").unwrap();
+
+        let tree = FileTree::from_directory(root.path().display().to_string(), None, &[], false);
+        let rendered = tree.to_file_tree(true);
+        let parsed = FileTree::from_file_tree(rendered.clone());
+
+        assert_eq!(parsed.path, tree.path);
+        assert_eq!(parsed.to_file_tree(true), rendered);
+    }
+
+    #[test]
+    fn test_from_file_tree_strips_index_prefixes() {
+        let rendered =
+            "Show\n├── File 1: a.mp4\n└── Directory 1: Season 2\n    └── File 1: b.mp4\n";
+        let tree = FileTree::from_file_tree(rendered.to_string());
+
+        assert_eq!(tree.files, ["Show/a.mp4"]);
+        assert_eq!(tree.directories[0].path, "Show/Season 2");
+        assert_eq!(tree.directories[0].files, ["Show/Season 2/b.mp4"]);
+    }
+
+    #[test]
+    fn test_from_file_tree_works_without_the_files_on_disk() {
+        let rendered = [
+            "/nas/Show",
+            "├── /nas/Show/a.mp4",
+            "├── /nas/Show/Extras/",
+            "├── /nas/Show/Season 1",
+            "│   └── /nas/Show/Season 1/b.mp4",
+            "└── /nas/Show/Specials",
+        ];
+        let tree = FileTree::from_file_tree(rendered.join("\n"));
+
+        assert_eq!(tree.files, ["/nas/Show/a.mp4"]);
+        let directories: Vec<&str> = tree.directories.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            directories,
+            [
+                "/nas/Show/Extras",
+                "/nas/Show/Season 1",
+                "/nas/Show/Specials"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display_renders_the_tree_by_reference() {
+        let mut tree = FileTree::new("/media/Show".to_string());
+        tree.files = vec!["/media/Show/a.mp4".to_string()];
+        let mut season = FileTree::new("/media/Show/Season 2".to_string());
+        season.files = vec!["/media/Show/Season 2/b.mp4".to_string()];
+        tree.directories.push(season);
+
+        assert_eq!(
+            tree.to_string(),
+            "Show\n├── a.mp4\n└── Season 2\n    └── b.mp4\n"
+        );
+        assert_eq!(format!("{:#}", tree), tree.to_file_tree(true));
+        assert_eq!(tree.files.len(), 1);
+    }
+
+    #[test]
+    fn test_to_file_tree_draws_continuation_bars() {
+        let directory = |path: &str, files: &[&str]| {
+            let mut tree = FileTree::new(path.to_string());
+            tree.files = files
+                .iter()
+                .map(|file| format!("{}/{}", path, file))
+                .collect();
+            tree
+        };
+        let mut tree = directory("/media/Show", &["poster.jpg"]);
+        let mut season_1 = directory("/media/Show/Season 1", &["Episode 1.mkv"]);
+        season_1
+            .directories
+            .push(directory("/media/Show/Season 1/Extras", &["Trailer.mkv"]));
+        tree.directories.push(season_1);
+        tree.directories
+            .push(directory("/media/Show/Season 2", &["Episode 1.mkv"]));
+
+        let expected = "\
+Show
+├── poster.jpg
+├── Season 1
+│   ├── Episode 1.mkv
+│   └── Extras
+│       └── Trailer.mkv
+└── Season 2
+    └── Episode 1.mkv
+";
+        assert_eq!(tree.to_file_tree(false), expected);
+    }
+
+    #[test]
+    fn test_to_markdown_nests_bullets_with_bold_directories() {
+        let tree = FileTree::from_string_vector(
+            [
+                "/media/Show/Season 10/Episode 1.mkv",
+                "/media/Show/Season 2/Episode 10.mkv",
+                "/media/Show/Season 2/Episode 2.mkv",
+                "/media/Show/Season 2/Extras/[Trailer] *Teaser*.mkv",
+                "/media/Show/poster.jpg",
+            ]
+            .iter()
+            .map(|path| path.to_string())
+            .collect(),
+        )
+        .unwrap();
+
+        let expected = "\
+- **Show**
+  - poster.jpg
+  - **Season 2**
+    - Episode 2.mkv
+    - Episode 10.mkv
+    - **Extras**
+      - \\[Trailer\\] \\*Teaser\\*.mkv
+  - **Season 10**
+    - Episode 1.mkv
+";
+        assert_eq!(tree.to_markdown(), expected);
+    }
+
+    #[test]
+    fn test_to_dot_links_each_directory_to_its_entries() {
+        let tree = FileTree::from_string_vector(
+            [
+                "/media/Show/Season 1/Episode \"1\".mkv",
+                "/media/Show/poster.jpg",
+            ]
+            .iter()
+            .map(|path| path.to_string())
+            .collect(),
+        )
+        .unwrap();
+
+        let expected = r#"digraph tree {
+    "/media/Show" [label="Show", shape=folder];
+    "/media/Show/poster.jpg" [label="poster.jpg", shape=note];
+    "/media/Show" -> "/media/Show/poster.jpg";
+    "/media/Show/Season 1" [label="Season 1", shape=folder];
+    "/media/Show" -> "/media/Show/Season 1";
+    "/media/Show/Season 1/Episode \"1\".mkv" [label="Episode \"1\".mkv", shape=note];
+    "/media/Show/Season 1" -> "/media/Show/Season 1/Episode \"1\".mkv";
+}
+"#;
+        assert_eq!(tree.to_dot(), expected);
+    }
+
+    #[test]
+    fn test_diff_lists_the_added_and_removed_files() {
+        let tree = |files: &[&str]| {
+            FileTree::from_string_vector(files.iter().map(|file| file.to_string()).collect())
+                .unwrap()
+        };
+        let old = tree(&[
+            "/m/Show/S1/e1.mkv",
+            "/m/Show/S1/e2.mkv",
+            "/m/Show/poster.jpg",
+        ]);
+        let new = tree(&[
+            "/m/Show/S1/e1.mkv",
+            "/m/Show/S1/e10.mkv",
+            "/m/Show/S1/e3.mkv",
+            "/m/Show/S2/e1.mkv",
+            "/m/Show/poster.jpg",
+        ]);
+
+        let changes = diff(&old, &new);
+
+        assert_eq!(
+            changes.added,
+            [
+                "/m/Show/S1/e3.mkv",
+                "/m/Show/S1/e10.mkv",
+                "/m/Show/S2/e1.mkv"
+            ]
+        );
+        assert_eq!(changes.removed, ["/m/Show/S1/e2.mkv"]);
+        assert_eq!(changes.to_string(), "3 files added, 1 removed");
+        assert!(diff(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,422 @@
+#[cfg(test)]
+mod tests {
+    use file_tree::{walk, FileTree, FileTreeError, ScanConfig, IGNORE_FILE_NAME};
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    // Builds root/a.mp4, root/1/b.mp4, root/1/2/c.mp4 and root/1/2/3/d.mp4
+    fn create_nested_fixture() -> TempDir {
+        let root = tempfile::tempdir().unwrap();
+        let mut dir = root.path().to_path_buf();
+        for (level, file) in ["a.mp4", "b.mp4", "c.mp4", "d.mp4"].iter().enumerate() {
+            if level > 0 {
+                dir = dir.join(level.to_string());
+                fs::create_dir(&dir).unwrap();
+            }
+            fs::write(dir.join(file), "").unwrap();
+        }
+        root
+    }
+
+    fn collect_file_names(tree: &FileTree, names: &mut Vec<String>) {
+        for file in &tree.files {
+            let name = Path::new(file).file_name().unwrap().to_string_lossy();
+            names.push(name.into_owned());
+        }
+        for directory in &tree.directories {
+            collect_file_names(directory, names);
+        }
+    }
+
+    fn scan(root: &TempDir, max_depth: Option<usize>) -> Vec<String> {
+        let path = root.path().display().to_string();
+        let tree = FileTree::try_from_directory(path, max_depth, &[], false).unwrap();
+        let mut names = Vec::new();
+        collect_file_names(&tree, &mut names);
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_max_depth_excludes_deeper_files() {
+        let root = create_nested_fixture();
+
+        assert_eq!(scan(&root, Some(2)), ["a.mp4", "b.mp4", "c.mp4"]);
+    }
+
+    #[test]
+    fn test_max_depth_zero_reads_only_the_root() {
+        let root = create_nested_fixture();
+
+        assert_eq!(scan(&root, Some(0)), ["a.mp4"]);
+    }
+
+    #[test]
+    fn test_no_max_depth_reads_everything() {
+        let root = create_nested_fixture();
+
+        assert_eq!(scan(&root, None), ["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+    }
+
+    #[test]
+    fn test_excluded_paths_are_not_listed() {
+        let root = create_nested_fixture();
+        fs::write(root.path().join("sample.mp4"), "").unwrap();
+        fs::create_dir(root.path().join("extras")).unwrap();
+        fs::write(root.path().join("extras").join("e.mp4"), "").unwrap();
+
+        let exclude = ["**/sample.*".to_string(), "**/extras".to_string()];
+        let tree =
+            FileTree::try_from_directory(root.path().display().to_string(), None, &exclude, false)
+                .unwrap();
+        let files = tree.to_file_list("");
+
+        assert_eq!(files.len(), 4);
+        assert!(files
+            .iter()
+            .all(|file| !file.contains("sample") && !file.contains("extras")));
+    }
+
+    #[test]
+    fn test_invalid_exclude_pattern_is_an_error() {
+        let root = create_nested_fixture();
+        let exclude = ["[".to_string()];
+
+        assert!(FileTree::try_from_directory(
+            root.path().display().to_string(),
+            None,
+            &exclude,
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_iter_files_walks_depth_first() {
+        let root = create_nested_fixture();
+        let tree =
+            FileTree::try_from_directory(root.path().display().to_string(), None, &[], false)
+                .unwrap();
+
+        let names: Vec<&str> = tree
+            .iter_files()
+            .map(|file| file.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(names, ["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+        assert_eq!(tree.iter_files().take(2).count(), 2);
+        assert_eq!(tree.to_file_list(""), tree.iter_files().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_borrowed_tree_iterates_over_its_files() {
+        let root = create_nested_fixture();
+        let tree =
+            FileTree::try_from_directory(root.path().display().to_string(), None, &[], false)
+                .unwrap();
+
+        let mut files = Vec::new();
+        for file in &tree {
+            files.push(file);
+        }
+        assert_eq!(files, tree.iter_files().collect::<Vec<_>>());
+        assert_eq!(
+            (&tree)
+                .into_iter()
+                .filter(|file| file.ends_with(".mp4"))
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let root = create_nested_fixture();
+        let tree =
+            FileTree::try_from_directory(root.path().display().to_string(), None, &[], false)
+                .unwrap();
+
+        let json = tree.to_json().unwrap();
+        let reloaded = FileTree::from_json(&json).unwrap();
+
+        assert_eq!(reloaded, tree);
+    }
+
+    #[test]
+    fn test_names_with_surrounding_spaces_are_kept() {
+        let root = tempfile::tempdir().unwrap();
+        let season = root.path().join("Season 1 ");
+        fs::create_dir(&season).unwrap();
+        fs::write(season.join(" a.mp4"), "").unwrap();
+
+        let tree =
+            FileTree::try_from_directory(root.path().display().to_string(), None, &[], false)
+                .unwrap();
+
+        let file = season.join(" a.mp4").display().to_string();
+        assert_eq!(tree.to_file_list(""), [file.as_str()]);
+        assert_eq!(tree.to_file_list("/mnt"), [format!("/mnt{}", file)]);
+        assert!(Path::new(&tree.to_file_list("")[0]).is_file());
+    }
+
+    #[test]
+    fn test_thread_count_does_not_change_the_tree() {
+        let root = create_nested_fixture();
+        let path = root.path().display().to_string();
+
+        let default = ScanConfig::new().build_from(path.clone()).unwrap();
+        for threads in [1, 2] {
+            let tree = ScanConfig::new()
+                .threads(threads)
+                .build_from(path.clone())
+                .unwrap();
+            assert_eq!(tree, default);
+        }
+    }
+
+    #[test]
+    fn test_directories_read_in_time_are_all_scanned() {
+        let root = create_nested_fixture();
+        let path = root.path().display().to_string();
+
+        let (tree, errors) = ScanConfig::new()
+            .scan_timeout(Duration::from_secs(30))
+            .build_from_with_errors(path.clone())
+            .unwrap();
+
+        assert_eq!(tree, ScanConfig::new().build_from(path).unwrap());
+        assert!(errors.is_empty());
+        let config: ScanConfig = serde_json::from_str(r#"{ "scan-timeout": 2.5 }"#).unwrap();
+        assert!(format!("{:?}", config).contains("scan_timeout: Some(2.5s)"));
+        assert!(serde_json::from_str::<ScanConfig>(r#"{ "scan-timeout": -1 }"#).is_err());
+    }
+
+    #[test]
+    fn test_max_files_stops_the_scan() {
+        let root = create_nested_fixture();
+        let path = root.path().display().to_string();
+
+        let config = ScanConfig::new().threads(1);
+        assert!(config.clone().max_files(4).build_from(path.clone()).is_ok());
+        let result = config.max_depth(1).max_files(1).build_from(path.clone());
+        assert!(matches!(
+            result,
+            Err(FileTreeError::TooManyFiles { limit: 1, path: stopped_at })
+                if stopped_at == root.path().join("1").display().to_string()
+        ));
+    }
+
+    #[test]
+    fn test_scan_config_is_reusable() {
+        let root = create_nested_fixture();
+        fs::write(root.path().join("notes.txt"), "").unwrap();
+        fs::write(root.path().join("1").join("E.MP4"), "").unwrap();
+
+        let config = ScanConfig::new().extensions([".mp4"]).max_depth(1);
+        for _ in 0..2 {
+            let tree = config
+                .build_from(root.path().display().to_string())
+                .unwrap();
+            let mut names = Vec::new();
+            collect_file_names(&tree, &mut names);
+            names.sort();
+            assert_eq!(names, ["E.MP4", "a.mp4", "b.mp4"]);
+        }
+    }
+
+    #[test]
+    fn test_subdirectories_are_in_natural_order() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["Season 10", "Season 2", "Season 1"] {
+            fs::create_dir(root.path().join(dir)).unwrap();
+        }
+
+        let tree = ScanConfig::new()
+            .build_from(root.path().display().to_string())
+            .unwrap();
+        let names: Vec<String> = tree
+            .directories
+            .iter()
+            .map(|dir| {
+                Path::new(&dir.path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(names, ["Season 1", "Season 2", "Season 10"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_self_referential_symlink_terminates() {
+        let root = create_nested_fixture();
+        std::os::unix::fs::symlink(root.path(), root.path().join("1").join("loop")).unwrap();
+        let path = root.path().display().to_string();
+
+        for follow_symlinks in [false, true] {
+            let config = ScanConfig::new().follow_symlinks(follow_symlinks);
+            let tree = config.build_from(path.clone()).unwrap();
+            let mut names = Vec::new();
+            collect_file_names(&tree, &mut names);
+            names.sort();
+            assert_eq!(names, ["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlink_is_skipped() {
+        let root = create_nested_fixture();
+        let target = root.path().join("gone.mp4");
+        std::os::unix::fs::symlink(&target, root.path().join("1").join("dangling.mp4")).unwrap();
+        let path = root.path().display().to_string();
+
+        for follow_symlinks in [false, true] {
+            let config = ScanConfig::new().follow_symlinks(follow_symlinks);
+            let tree = config.build_from(path.clone()).unwrap();
+            let mut names = Vec::new();
+            collect_file_names(&tree, &mut names);
+            names.sort();
+            assert_eq!(names, ["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_directories_are_followed_on_request() {
+        let root = create_nested_fixture();
+        let other = tempfile::tempdir().unwrap();
+        fs::write(other.path().join("e.mp4"), "").unwrap();
+        std::os::unix::fs::symlink(other.path(), root.path().join("linked")).unwrap();
+        let path = root.path().display().to_string();
+
+        let scan = |follow_symlinks| {
+            let config = ScanConfig::new().follow_symlinks(follow_symlinks);
+            let tree = config.build_from(path.clone()).unwrap();
+            let mut names = Vec::new();
+            collect_file_names(&tree, &mut names);
+            names.contains(&"e.mp4".to_string())
+        };
+        assert!(!scan(false));
+        assert!(scan(true));
+    }
+
+    #[test]
+    fn test_excludes_win_over_includes() {
+        let root = create_nested_fixture();
+        fs::write(root.path().join("1").join("b.srt"), "").unwrap();
+
+        let config = ScanConfig::new()
+            .include(["**/1/**/*.mp4"])
+            .exclude(["**/3/**"]);
+        let tree = config
+            .build_from(root.path().display().to_string())
+            .unwrap();
+        let mut names = Vec::new();
+        collect_file_names(&tree, &mut names);
+        names.sort();
+        assert_eq!(names, ["b.mp4", "c.mp4"]);
+    }
+
+    #[test]
+    fn test_invalid_include_pattern_is_reported() {
+        let root = create_nested_fixture();
+
+        let result = ScanConfig::new()
+            .include(["[unclosed"])
+            .build_from(root.path().display().to_string());
+        assert!(matches!(
+            result,
+            Err(FileTreeError::InvalidPattern { pattern, .. }) if pattern == "[unclosed"
+        ));
+    }
+
+    #[test]
+    fn test_walk_applies_the_scan_filters() {
+        let root = create_nested_fixture();
+        fs::write(root.path().join("1").join("b.srt"), "subtitle").unwrap();
+        fs::write(root.path().join(".hidden.mp4"), "").unwrap();
+        let config = ScanConfig::new()
+            .extensions([".SRT"])
+            .max_depth(1)
+            .exclude(["**/2"]);
+
+        let walked = walk(root.path(), &config).unwrap();
+
+        let paths: Vec<_> = walked.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths, [root.path().join("1"), root.path().join("1/b.srt")]);
+        assert!(walked[0].1.is_dir());
+        assert_eq!(walked[1].1.len(), 8);
+    }
+
+    #[test]
+    fn test_scan_config_deserializes_with_defaults() {
+        let root = create_nested_fixture();
+        fs::write(root.path().join("a.MKV"), "").unwrap();
+        let config: ScanConfig =
+            serde_json::from_str(r#"{ "extensions": [".MkV"], "max-depth": 0 }"#).unwrap();
+
+        let tree = config
+            .build_from(root.path().display().to_string())
+            .unwrap();
+
+        assert_eq!(
+            tree.files,
+            [root.path().join("a.MKV").display().to_string()]
+        );
+        assert!(tree.directories.is_empty());
+        assert!(serde_json::from_str::<ScanConfig>(r#"{ "depth": 1 }"#).is_err());
+    }
+
+    #[test]
+    fn test_ripignore_patterns_stack_down_the_tree() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["Extras", "Season 1/Extras", "Season 1/Samples", "Season 2"] {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        for file in [
+            "a.mp4",
+            "a.nfo",
+            "Extras/x.mp4",
+            "Season 1/e1.mp4",
+            "Season 1/e1.nfo",
+            "Season 1/Extras/x.mp4",
+            "Season 1/Samples/s.mp4",
+            "Season 2/e1.mp4",
+            "Season 2/sample.mp4",
+        ] {
+            fs::write(root.path().join(file), "").unwrap();
+        }
+        // `*.nfo` and `Extras` apply at any depth, `Samples` only from Season 1
+        fs::write(
+            root.path().join(IGNORE_FILE_NAME),
+            "# metadata\n*.nfo\n\nExtras/\n",
+        )
+        .unwrap();
+        fs::write(
+            root.path().join("Season 1").join(IGNORE_FILE_NAME),
+            "/Samples\n",
+        )
+        .unwrap();
+        fs::write(
+            root.path().join("Season 2").join(IGNORE_FILE_NAME),
+            "sample.*\n",
+        )
+        .unwrap();
+
+        let path = root.path().display().to_string();
+        let mut names = Vec::new();
+        collect_file_names(
+            &FileTree::from_directory(path, None, &[], false),
+            &mut names,
+        );
+        names.sort();
+
+        assert_eq!(names, ["a.mp4", "e1.mp4", "e1.mp4"]);
+        let walked = walk(root.path(), &ScanConfig::new()).unwrap();
+        assert_eq!(walked.len(), 5);
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use file_tree::natural_cmp;
+
+    #[test]
+    fn test_natural_cmp_orders_numbers_by_value() {
+        let mut files = vec![
+            "file20.mp4",
+            "Episode 10.mp4",
+            "file2.mp4",
+            "Episode 2.mp4",
+            "file10.mp4",
+            "Episode 1.mp4",
+        ];
+        files.sort_by(|a, b| natural_cmp(a, b));
+
+        assert_eq!(
+            files,
+            [
+                "Episode 1.mp4",
+                "Episode 2.mp4",
+                "Episode 10.mp4",
+                "file2.mp4",
+                "file10.mp4",
+                "file20.mp4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_natural_cmp_handles_leading_zeros() {
+        let mut files = vec!["S01E10", "S01E02", "S1E3", "S01E01"];
+        files.sort_by(|a, b| natural_cmp(a, b));
+
+        assert_eq!(files, ["S01E01", "S01E02", "S1E3", "S01E10"]);
+    }
+}
@@ -0,0 +1,1428 @@
+#[cfg(test)]
+mod tests {
+    use file_tree::{
+        apply, apply_with_progress, check_disjoint, expand_destination, extension_of, parse_since,
+        parse_size, sanitize_link_name, FileTree, FileTreeError, GroupBy, GroupSort, GroupingType,
+        LinkMode, LinkOptions, PlannedAction, RenamePattern, SortOrder, DEFAULT_SPECIALS_KEYWORDS,
+    };
+    use std::fs;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_extension_of() {
+        assert_eq!(extension_of("/media/Show/Episode 1.mkv"), ".mkv");
+        assert_eq!(extension_of("/media/Show/Episode.1.MP4"), ".MP4");
+        assert_eq!(extension_of("/media/Show/README"), "");
+    }
+
+    #[test]
+    fn test_links_keep_the_source_extension() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("Pilot.mkv"), "").unwrap();
+
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let options = LinkOptions {
+            extensions: vec![".mkv".to_string()],
+            grouping: GroupingType::Plex,
+            ..Default::default()
+        };
+        tree.create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        let show = source.path().file_name().unwrap().to_string_lossy();
+        let link = destination
+            .path()
+            .join("Season 01")
+            .join(format!("{} - S01E01.mkv", show));
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+    }
+
+    #[test]
+    fn test_rerun_skips_existing_links() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        fs::write(source.path().join("b.mp4"), "").unwrap();
+
+        // Linking borrows the tree, so the same one is linked twice
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let link = || {
+            tree.create_grouped_symlinks(
+                destination.path().display().to_string(),
+                &LinkOptions::default(),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(link().created.len(), 2);
+        let summary = link();
+        assert_eq!(
+            (
+                summary.created.len(),
+                summary.skipped.len(),
+                summary.replaced.len()
+            ),
+            (0, 2, 0)
+        );
+        assert_eq!(tree.count_files(), 2);
+    }
+
+    #[test]
+    fn test_failed_links_are_reported() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for season in ["a", "b"] {
+            fs::create_dir(source.path().join(season)).unwrap();
+            fs::write(source.path().join(season).join("Pilot.mp4"), "").unwrap();
+        }
+        // A file where the second season's directory goes
+        fs::write(destination.path().join("Season 02"), "not a directory").unwrap();
+
+        let options = LinkOptions {
+            grouping: GroupingType::Plex,
+            ..Default::default()
+        };
+        let report =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+        let show = source.path().file_name().unwrap().to_string_lossy();
+        let blocked = destination
+            .path()
+            .join("Season 02")
+            .join(format!("{} - S02E01.mp4", show));
+        assert_eq!(
+            report.created,
+            [destination
+                .path()
+                .join("Season 01")
+                .join(format!("{} - S01E01.mp4", show))]
+        );
+        assert!(!report.is_success());
+        assert!(matches!(
+            report.failures.as_slice(),
+            [FileTreeError::Io { path, .. }] if Path::new(path) == blocked
+        ));
+    }
+
+    #[test]
+    fn test_subtitles_are_linked_next_to_their_video() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["Pilot.mkv", "Pilot.srt", "Pilot.en.srt", "Pilot 2.srt"] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let options = LinkOptions {
+            extensions: vec![".mkv".to_string()],
+            ..Default::default()
+        };
+        let summary = tree
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        assert_eq!(summary.created.len(), 3);
+        for link in [
+            "S01E01 - Pilot.mkv",
+            "S01E01 - Pilot.srt",
+            "S01E01 - Pilot.en.srt",
+        ] {
+            assert!(fs::symlink_metadata(destination.path().join(link)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_companion_files_are_linked_next_to_their_video() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["Pilot.mkv", "Pilot.JPG", "Pilot.nfo", "Pilot.txt"] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let options = LinkOptions {
+            extensions: vec![".mkv".to_string()],
+            companion_extensions: vec![".jpg".to_string(), ".nfo".to_string()],
+            grouping: GroupingType::Plex,
+            write_nfo: true,
+            ..Default::default()
+        };
+        let summary = tree
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        let show = source.path().file_name().unwrap().to_string_lossy();
+        let season = destination.path().join("Season 01");
+        assert_eq!(summary.created.len(), 3);
+        for extension in ["JPG", "nfo"] {
+            let link = season.join(format!("{} - S01E01.{}", show, extension));
+            assert!(fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink());
+        }
+        // The companion .nfo is linked instead of a generated one
+        assert_eq!(
+            fs::read_to_string(source.path().join("Pilot.nfo")).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_only_the_preferred_quality_is_linked() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in [
+            "Episode.1.720p.mp4",
+            "Episode.1.1080p.mkv",
+            "Episode.2.720p.mp4",
+            "Episode.3.mkv",
+            "Episode.3.mp4",
+        ] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let options = LinkOptions {
+            extensions: vec![".mkv".to_string(), ".mp4".to_string()],
+            grouping: GroupingType::Plex,
+            quality_priority: vec!["1080P".to_string(), "720p".to_string(), ".mkv".to_string()],
+            ..Default::default()
+        };
+        let summary = tree
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        let show = source.path().file_name().unwrap().to_string_lossy();
+        let season = destination.path().join("Season 01");
+        assert_eq!(summary.created.len(), 3);
+        for (episode, file) in [
+            ("S01E01.mkv", "Episode.1.1080p.mkv"),
+            ("S01E02.mp4", "Episode.2.720p.mp4"),
+            ("S01E03.mkv", "Episode.3.mkv"),
+        ] {
+            let link = season.join(format!("{} - {}", show, episode));
+            assert_eq!(fs::read_link(link).unwrap(), source.path().join(file));
+        }
+    }
+
+    #[test]
+    fn test_files_in_the_way_are_only_replaced_with_force() {
+        for link_mode in [LinkMode::Symlink, LinkMode::Copy] {
+            let source = tempfile::tempdir().unwrap();
+            let destination = tempfile::tempdir().unwrap();
+            fs::write(source.path().join("Pilot.mp4"), "pilot").unwrap();
+            fs::write(source.path().join("Finale.mp4"), "finale").unwrap();
+            let file = destination.path().join("S01E01 - Finale.mp4");
+            let directory = destination.path().join("S01E02 - Pilot.mp4");
+            fs::write(&file, "leftover").unwrap();
+            fs::create_dir(&directory).unwrap();
+
+            let tree =
+                || FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+            let mut options = LinkOptions {
+                link_mode,
+                ..Default::default()
+            };
+            let report = tree()
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+            assert!(!report.is_success());
+            assert!(report.failures.is_empty());
+            assert_eq!(report.conflicts, [file.as_path(), directory.as_path()]);
+            assert!(report.mappings.is_empty());
+            assert_eq!(fs::read_to_string(&file).unwrap(), "leftover");
+
+            options.force = true;
+            let report = tree()
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+            assert_eq!(report.replaced, [file.as_path()]);
+            assert_eq!(report.conflicts, [directory.as_path()]);
+            match link_mode {
+                LinkMode::Copy => assert_eq!(fs::read_to_string(&file).unwrap(), "finale"),
+                _ => assert_eq!(
+                    fs::read_link(&file).unwrap(),
+                    source.path().join("Finale.mp4")
+                ),
+            }
+            assert!(directory.is_dir());
+        }
+    }
+
+    #[test]
+    fn test_multi_root_tree_is_linked_by_root() {
+        let source = tempfile::tempdir().unwrap();
+        let roots = [source.path().join("1/Show"), source.path().join("2/More")];
+        for (root, file) in roots.iter().zip(["a.mp4", "b.mp4"]) {
+            fs::create_dir_all(root).unwrap();
+            fs::write(root.join(file), "").unwrap();
+        }
+        let tree = FileTree::from_directories(
+            roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect(),
+        );
+        let destination = source.path().join("library");
+
+        for link_mode in [LinkMode::Symlink, LinkMode::Hardlink, LinkMode::Copy] {
+            let layouts = [
+                (
+                    LinkOptions {
+                        grouping: GroupingType::Plex,
+                        ..Default::default()
+                    },
+                    ["Season 01/Show - S01E01.mp4", "Season 02/Show - S02E01.mp4"],
+                ),
+                (
+                    LinkOptions {
+                        flatten: true,
+                        ..Default::default()
+                    },
+                    ["0001 - a.mp4", "0002 - b.mp4"],
+                ),
+                (
+                    LinkOptions {
+                        mirror: true,
+                        ..Default::default()
+                    },
+                    ["Show/a.mp4", "More/b.mp4"],
+                ),
+            ];
+            for (options, expected) in layouts {
+                let options = LinkOptions {
+                    link_mode,
+                    ..options
+                };
+                let links: Vec<_> = tree
+                    .plan(&destination, &options)
+                    .unwrap()
+                    .iter()
+                    .filter(|action| !matches!(action, PlannedAction::CreateDir { .. }))
+                    .map(|action| {
+                        action
+                            .target()
+                            .strip_prefix(&destination)
+                            .unwrap()
+                            .to_owned()
+                    })
+                    .collect();
+                assert_eq!(links, expected.map(Path::new));
+            }
+        }
+
+        // Each root is checked against the destination, the synthetic root is not
+        assert!(tree.plan(".", &LinkOptions::default()).is_ok());
+        assert!(matches!(
+            tree.plan(roots[1].join("library"), &LinkOptions::default()),
+            Err(FileTreeError::OverlappingPaths { .. })
+        ));
+    }
+
+    #[test]
+    fn test_relative_links_point_up_to_the_source() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("shows").join("Show");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.mp4"), "a").unwrap();
+        let destination = root.path().join("library");
+
+        let options = LinkOptions {
+            grouping: GroupingType::Plex,
+            relative: true,
+            ..Default::default()
+        };
+        FileTree::from_directory(source.display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.display().to_string(), &options)
+            .unwrap();
+
+        let link = destination.join("Season 01").join("Show - S01E01.mp4");
+        assert_eq!(
+            fs::read_link(&link).unwrap(),
+            Path::new("../../shows/Show/a.mp4")
+        );
+        assert_eq!(fs::read_to_string(&link).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_copy_mode_copies_the_files() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("Pilot.mp4"), "pilot").unwrap();
+        let copy = destination.path().join("S01E01 - Pilot.mp4");
+
+        let mut options = LinkOptions {
+            link_mode: LinkMode::Copy,
+            ..Default::default()
+        };
+        let link = |options: &LinkOptions| {
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), options)
+                .unwrap()
+        };
+
+        assert_eq!(link(&options).created, [copy.as_path()]);
+        assert!(fs::symlink_metadata(&copy).unwrap().is_file());
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "pilot");
+        assert_eq!(link(&options).skipped, [copy.as_path()]);
+
+        // An interrupted copy cannot be told apart from another file, it is only
+        // redone with force
+        fs::write(&copy, "pi").unwrap();
+        assert_eq!(link(&options).conflicts, [copy.as_path()]);
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "pi");
+        options.force = true;
+        assert_eq!(link(&options).replaced, [copy.as_path()]);
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "pilot");
+    }
+
+    #[test]
+    fn test_verify_checks_the_new_links() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "a").unwrap();
+        let mut tree =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        // A directory listed as a file: its link does not resolve to a file and it
+        // cannot be copied
+        let directory = source.path().join("b.mp4");
+        fs::create_dir(&directory).unwrap();
+        tree.files.push(directory.display().to_string());
+
+        for link_mode in [LinkMode::Symlink, LinkMode::Copy] {
+            let destination = destination.path().join(format!("{:?}", link_mode));
+            let options = LinkOptions {
+                link_mode,
+                verify: true,
+                ..Default::default()
+            };
+            let report = FileTree::from_json(&tree.to_json().unwrap())
+                .unwrap()
+                .create_grouped_symlinks(destination.display().to_string(), &options)
+                .unwrap();
+
+            assert_eq!(report.verified, [destination.join("S01E01 - a.mp4")]);
+            assert_eq!(report.failures.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_missing_sources_are_not_linked() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        fs::write(source.path().join("b.mp4"), "").unwrap();
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        fs::remove_file(source.path().join("b.mp4")).unwrap();
+        let json = tree.to_json().unwrap();
+
+        let strict = LinkOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let result = FileTree::from_json(&json)
+            .unwrap()
+            .create_grouped_symlinks(destination.path().display().to_string(), &strict);
+        assert!(matches!(result, Err(FileTreeError::MissingSource { .. })));
+        assert_eq!(fs::read_dir(destination.path()).unwrap().count(), 0);
+
+        let report = tree
+            .create_grouped_symlinks(
+                destination.path().display().to_string(),
+                &LinkOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(report.missing, [source.path().join("b.mp4")]);
+        assert_eq!(report.created, [destination.path().join("S01E01 - a.mp4")]);
+    }
+
+    #[test]
+    fn test_link_paths_are_normalized() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+
+        let report =
+            FileTree::from_directory(format!("{}//./", source.path().display()), None, &[], false)
+                .create_grouped_symlinks(
+                    format!("{}/./Links//", destination.path().display()),
+                    &LinkOptions::default(),
+                )
+                .unwrap();
+
+        let link = destination.path().join("Links").join("S01E01 - a.mp4");
+        assert_eq!(report.created, [link.as_path()]);
+        assert_eq!(fs::read_link(&link).unwrap(), source.path().join("a.mp4"));
+    }
+
+    #[test]
+    fn test_csv_maps_sources_to_links() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["A/a.mp4", "B, the second/b.mp4"] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            season_start: Some(2),
+            ..Default::default()
+        };
+        let report =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+        let (source, destination) = (source.path().display(), destination.path().display());
+        assert_eq!(
+            report.to_csv(),
+            format!(
+                "group,index,source_path,link_path,season,episode\n\
+                 A,1,{0}/A/a.mp4,{1}/S02E01 - a.mp4,2,1\n\
+                 \"B, the second\",2,\"{0}/B, the second/b.mp4\",{1}/S03E01 - b.mp4,3,1\n",
+                source, destination
+            )
+        );
+    }
+
+    #[test]
+    fn test_multi_part_episodes_share_a_number() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for name in ["A - part1.mp4", "A - part2.mp4", "B.mp4"] {
+            fs::write(source.path().join(name), "").unwrap();
+        }
+
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(
+                destination.path().display().to_string(),
+                &LinkOptions::default(),
+            )
+            .unwrap();
+
+        for link in [
+            "S01E01-part1 - A - part1.mp4",
+            "S01E01-part2 - A - part2.mp4",
+            "S01E02 - B.mp4",
+        ] {
+            assert!(fs::symlink_metadata(destination.path().join(link)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_hardlink_mode_links_the_same_file() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("Pilot.mp4"), "pilot").unwrap();
+
+        let options = LinkOptions {
+            link_mode: LinkMode::Hardlink,
+            ..Default::default()
+        };
+        let link = || {
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap()
+        };
+
+        assert_eq!(link().created.len(), 1);
+        let metadata = fs::symlink_metadata(destination.path().join("S01E01 - Pilot.mp4")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(link().skipped.len(), 1);
+    }
+
+    // Links Show/{a,b}.mp4, Show/A/c.mp4 and Show/B/d.mp4 and returns the link names
+    fn link_names(group_by: GroupBy) -> Vec<String> {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["a.mp4", "b.mp4", "A/c.mp4", "B/d.mp4"] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            group_by,
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(destination.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_group_by_parent_dir_gives_sibling_directories_their_own_season() {
+        assert_eq!(
+            link_names(GroupBy::ParentDir),
+            [
+                "S01E01 - a.mp4",
+                "S01E02 - b.mp4",
+                "S02E01 - c.mp4",
+                "S03E01 - d.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_filename_gives_every_file_its_own_season() {
+        assert_eq!(
+            link_names(GroupBy::Filename),
+            [
+                "S01E01 - a.mp4",
+                "S02E01 - b.mp4",
+                "S03E01 - c.mp4",
+                "S04E01 - d.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_regex_buckets_by_capture_and_ungroups_the_rest() {
+        assert_eq!(
+            link_names(GroupBy::Regex(r"^(?P<group>[bc])\.".to_string())),
+            [
+                "S01E01 - b.mp4",
+                "S02E01 - c.mp4",
+                "S03E01 - a.mp4",
+                "S03E02 - d.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_regex_requires_a_group_capture() {
+        assert!(GroupBy::Regex("(?P<group>.+)".to_string())
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            GroupBy::Regex("(.+)".to_string()).validate(),
+            Err(FileTreeError::MissingGroupCapture { .. })
+        ));
+        assert!(matches!(
+            GroupBy::Regex("(?P<group>".to_string()).validate(),
+            Err(FileTreeError::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_group_template_names_the_season_directories() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["a.mp4", "b.mp4", "c.mp4"] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let options = LinkOptions {
+            group_by: GroupBy::Regex(r"^(?P<group>[bc])\.".to_string()),
+            group_template: Some("{type} {index:02} - {name} {{x}}".to_string()),
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        for link in [
+            "Group 01 - b {x}/S01E01 - b.mp4",
+            "Group 02 - c {x}/S02E01 - c.mp4",
+            "Group 03 - Ungrouped {x}/S03E01 - a.mp4",
+        ] {
+            assert!(
+                destination.path().join(link).is_file(),
+                "{} is missing",
+                link
+            );
+        }
+    }
+
+    #[test]
+    fn test_group_template_rejects_unknown_placeholders() {
+        let options = |template: &str| LinkOptions {
+            group_template: Some(template.to_string()),
+            ..Default::default()
+        };
+        assert!(options("Season {index:02} - {name}").validate().is_ok());
+        for template in ["{season}", "{name:02}", "{index:x}", "Season {index", "}"] {
+            assert!(
+                matches!(
+                    options(template).validate(),
+                    Err(FileTreeError::InvalidTemplate { .. })
+                ),
+                "{} was accepted",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_destination() {
+        assert_eq!(
+            expand_destination("{source}/../_plex/{show}", "/media/Show/").unwrap(),
+            "/media/Show//../_plex/Show"
+        );
+        assert_eq!(
+            expand_destination("/links/{{{show}}}", "/media/Show").unwrap(),
+            "/links/{Show}"
+        );
+        assert_eq!(
+            expand_destination("/links", "/media/Show").unwrap(),
+            "/links"
+        );
+
+        let date = expand_destination("{date}", "/media/Show").unwrap();
+        assert_eq!(date.len(), 10);
+        assert!(date.starts_with("20"));
+        assert_eq!(&date[4..5], "-");
+
+        for template in ["{season}", "{show", "}"] {
+            assert!(matches!(
+                expand_destination(template, "/media/Show"),
+                Err(FileTreeError::InvalidTemplate { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_flatten_numbers_every_file_in_one_directory() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in [
+            "Season 10/a.mp4",
+            "Season 2/b.mp4",
+            "Season 2/b.srt",
+            "c.mp4",
+        ] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            extensions: vec![".mp4".to_string()],
+            flatten: true,
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(destination.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "0001 - b.mp4",
+                "0001 - b.srt",
+                "0002 - a.mp4",
+                "0003 - c.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mirror_keeps_the_source_layout() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["Season 1/a.mp4", "Season 1/a.srt", "Extras/b.txt", "c.mp4"] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            mirror: true,
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        for file in ["Season 1/a.mp4", "Season 1/a.srt", "c.mp4"] {
+            let link = destination.path().join(file);
+            assert_eq!(fs::read_link(&link).unwrap(), source.path().join(file));
+        }
+        assert!(!destination.path().join("Extras").exists());
+    }
+
+    #[test]
+    fn test_numbering_starts_at_the_given_season_and_episode() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["A/a1.mp4", "A/a2.mp4", "B/b1.mp4"] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            group_template: Some("Season {index}".to_string()),
+            season_start: Some(3),
+            episode_start: Some(45),
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        for link in [
+            "Season 3/S03E45 - a1.mp4",
+            "Season 3/S03E46 - a2.mp4",
+            "Season 4/S04E01 - b1.mp4",
+        ] {
+            assert!(fs::symlink_metadata(destination.path().join(link)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_specials_are_numbered_in_season_0() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in [
+            "Season 1/e1.mp4",
+            "Season 1/e2 OVA.mp4",
+            "Season 1/e3.mp4",
+            "Season 2/f1.mp4",
+            "Specials/Behind the scenes.mp4",
+        ] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            grouping: GroupingType::Plex,
+            season_start: Some(2),
+            specials: DEFAULT_SPECIALS_KEYWORDS.map(String::from).to_vec(),
+            ..Default::default()
+        };
+        let report =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+        let show = source.path().file_name().unwrap().to_string_lossy();
+        for (link, file) in [
+            ("Season 02/{} - S02E01.mp4", "Season 1/e1.mp4"),
+            ("Season 02/{} - S02E02.mp4", "Season 1/e3.mp4"),
+            ("Season 03/{} - S03E01.mp4", "Season 2/f1.mp4"),
+            ("Season 00/{} - S00E01.mp4", "Season 1/e2 OVA.mp4"),
+            (
+                "Season 00/{} - S00E02.mp4",
+                "Specials/Behind the scenes.mp4",
+            ),
+        ] {
+            let link = destination.path().join(link.replace("{}", &show));
+            assert_eq!(fs::read_link(link).unwrap(), source.path().join(file));
+        }
+        assert!(!destination.path().join("Season 04").exists());
+        let specials = &report.mappings[3..];
+        assert!(specials
+            .iter()
+            .all(|mapping| mapping.group == "Specials" && mapping.index == 0));
+    }
+
+    #[test]
+    fn test_season_start_0_is_rejected_along_with_specials() {
+        let options = LinkOptions {
+            season_start: Some(0),
+            specials: DEFAULT_SPECIALS_KEYWORDS.map(String::from).to_vec(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(FileTreeError::InvalidOptions { .. })
+        ));
+
+        let options = LinkOptions {
+            season_start: Some(0),
+            ..Default::default()
+        };
+        assert!(options.validate().is_ok());
+    }
+
+    #[test]
+    fn test_link_directories_links_each_season_directory() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in [
+            "Season 1/a.mp4",
+            "Season 1/a.srt",
+            "Season 2/b.mp4",
+            "Notes/x.txt",
+        ] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let options = LinkOptions {
+            grouping: GroupingType::Plex,
+            link_directories: true,
+            verify: true,
+            ..Default::default()
+        };
+        let link = || {
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap()
+        };
+        let report = link();
+
+        assert!(report.is_success());
+        assert_eq!(report.created.len(), 2);
+        assert_eq!(report.verified.len(), 2);
+        for (link, directory) in [("Season 01", "Season 1"), ("Season 02", "Season 2")] {
+            let link = destination.path().join(link);
+            assert_eq!(fs::read_link(&link).unwrap(), source.path().join(directory));
+        }
+        assert!(destination.path().join("Season 01").join("a.srt").exists());
+        assert!(!destination.path().join("Season 03").exists());
+        assert_eq!(link().skipped.len(), 2);
+
+        let options = LinkOptions {
+            link_directories: true,
+            link_mode: LinkMode::Hardlink,
+            ..Default::default()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(FileTreeError::InvalidOptions { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rename_patterns_rewrite_the_link_names() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in [
+            "[HorribleSubs] Pilot [1080p].mp4",
+            "[HorribleSubs] Pilot [1080p].en.srt",
+        ] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let options = LinkOptions {
+            rename_patterns: vec![
+                r"\[[^]]*\] *=>".parse().unwrap(),
+                r"Pilot=>Pilot (Uncut)".parse().unwrap(),
+            ],
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        for link in [
+            "S01E01 - Pilot (Uncut).mp4",
+            "S01E01 - Pilot (Uncut).en.srt",
+        ] {
+            assert!(fs::symlink_metadata(destination.path().join(link)).is_ok());
+        }
+        assert!(matches!(
+            "no arrow".parse::<RenamePattern>(),
+            Err(FileTreeError::InvalidRenamePattern { .. })
+        ));
+        assert!(matches!(
+            "(=>x".parse::<RenamePattern>(),
+            Err(FileTreeError::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_link_extensions_are_lowercased_when_normalized() {
+        let source = tempfile::tempdir().unwrap();
+        for file in [
+            "Episode 1.MKV",
+            "Episode 2.Mp4",
+            "Episode 2.EN.SRT",
+            "Episode 3.mkv",
+        ] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+        let link = |normalize_extension_case| {
+            let destination = tempfile::tempdir().unwrap();
+            let options = LinkOptions {
+                extensions: vec![".mkv".to_string(), ".mp4".to_string()],
+                normalize_extension_case,
+                ..Default::default()
+            };
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+            let mut links: Vec<String> = fs::read_dir(destination.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            links.sort();
+            links
+        };
+
+        assert_eq!(
+            link(true),
+            [
+                "S01E01 - Episode 1.mkv",
+                "S01E02 - Episode 2.EN.srt",
+                "S01E02 - Episode 2.mp4",
+                "S01E03 - Episode 3.mkv",
+            ]
+        );
+        assert_eq!(
+            link(false),
+            [
+                "S01E01 - Episode 1.MKV",
+                "S01E02 - Episode 2.EN.SRT",
+                "S01E02 - Episode 2.Mp4",
+                "S01E03 - Episode 3.mkv",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_order_decides_the_episode_numbers() {
+        let source = tempfile::tempdir().unwrap();
+        let files =
+            ["Part 10.mp4", "Part 2.mp4", "Part 1.mp4"].map(|file| source.path().join(file));
+        for file in &files {
+            fs::write(file, "").unwrap();
+        }
+        let numbered = |sort_order| {
+            let destination = tempfile::tempdir().unwrap();
+            let mut tree = FileTree::new(source.path().display().to_string());
+            tree.files = files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            let options = LinkOptions {
+                sort_order,
+                ..Default::default()
+            };
+            tree.create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+            let mut names: Vec<String> = fs::read_dir(destination.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            numbered(SortOrder::Natural),
+            [
+                "S01E01 - Part 1.mp4",
+                "S01E02 - Part 2.mp4",
+                "S01E03 - Part 10.mp4"
+            ]
+        );
+        assert_eq!(
+            numbered(SortOrder::Alphabetical),
+            [
+                "S01E01 - Part 1.mp4",
+                "S01E02 - Part 10.mp4",
+                "S01E03 - Part 2.mp4"
+            ]
+        );
+        assert_eq!(
+            numbered(SortOrder::AsScanned),
+            [
+                "S01E01 - Part 10.mp4",
+                "S01E02 - Part 2.mp4",
+                "S01E03 - Part 1.mp4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sanitize_link_name_keeps_the_extension() {
+        assert_eq!(sanitize_link_name("What? A/B: C*.mkv"), "What_ A_B_ C_.mkv");
+
+        let long = format!("{}.mkv", "é".repeat(200));
+        let sanitized = sanitize_link_name(&long);
+        assert!(sanitized.len() <= 255);
+        assert!(sanitized.ends_with("éé.mkv"));
+    }
+
+    #[test]
+    fn test_long_names_are_truncated() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let stem = "a".repeat(244);
+        fs::write(source.path().join(format!("{}.mkv", stem)), "").unwrap();
+        fs::write(source.path().join(format!("{}.en.srt", stem)), "").unwrap();
+
+        let options = LinkOptions {
+            extensions: vec![".mkv".to_string()],
+            ..Default::default()
+        };
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let summary = tree
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        assert_eq!(summary.created.len(), 2);
+        let mut names: Vec<String> = fs::read_dir(destination.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names[0].len(), 255);
+        assert!(names[0].starts_with("S01E01 - aaa") && names[0].ends_with("a.en.srt"));
+        assert!(names[1].starts_with("S01E01 - aaa") && names[1].ends_with("a.mkv"));
+    }
+
+    #[test]
+    fn test_check_disjoint_rejects_nested_destinations() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("Show");
+        fs::create_dir(&source).unwrap();
+
+        let overlaps = |destination: &Path| {
+            matches!(
+                check_disjoint(&source, destination),
+                Err(FileTreeError::OverlappingPaths { .. })
+            )
+        };
+        assert!(overlaps(&source));
+        assert!(overlaps(&source.join("Links/Season 01")));
+        assert!(overlaps(&source.join("..")));
+        assert!(!overlaps(&root.path().join("Show Links")));
+    }
+
+    #[test]
+    fn test_nothing_is_linked_inside_the_source() {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let destination = source.path().join("Links");
+
+        let result =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(
+                    destination.display().to_string(),
+                    &LinkOptions::default(),
+                );
+
+        assert!(matches!(
+            result,
+            Err(FileTreeError::OverlappingPaths { .. })
+        ));
+        assert!(!destination.exists());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("50MB").unwrap(), 50_000_000);
+        assert_eq!(parse_size("1.5 GiB").unwrap(), 1_610_612_736);
+        assert_eq!(parse_size("2k").unwrap(), 2048);
+        for invalid in ["", "MB", "-1MB", "5XB", "1.2.3"] {
+            assert!(
+                matches!(parse_size(invalid), Err(FileTreeError::InvalidSize { .. })),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_min_size_skips_small_files() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("Episode 1.mp4"), vec![0; 4096]).unwrap();
+        fs::write(source.path().join("sample.mp4"), vec![0; 512]).unwrap();
+        fs::write(source.path().join("Episode 2.mp4"), vec![0; 2048]).unwrap();
+
+        let options = LinkOptions {
+            min_size: Some(parse_size("2KiB").unwrap()),
+            ..Default::default()
+        };
+        let summary =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+        assert_eq!(summary.created.len(), 2);
+        assert!(destination.path().join("S01E01 - Episode 1.mp4").exists());
+        assert!(destination.path().join("S01E02 - Episode 2.mp4").exists());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+        assert_eq!(parse_since("1970-01-02").unwrap(), at(86_400));
+        assert_eq!(
+            parse_since("2024-02-29T12:30:15Z").unwrap(),
+            at(1_709_209_815)
+        );
+        assert_eq!(parse_since("2024-02-29 12:30").unwrap(), at(1_709_209_800));
+        let since = parse_since("7d").unwrap();
+        let week_ago = SystemTime::now() - Duration::from_secs(7 * 86_400);
+        assert!(since <= week_ago && week_ago.duration_since(since).unwrap().as_secs() < 60);
+        for invalid in [
+            "",
+            "7y",
+            "d",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-01-01T24:00",
+            "1969-12-31",
+        ] {
+            assert!(
+                matches!(parse_since(invalid), Err(FileTreeError::InvalidDate { .. })),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_modified_since_skips_older_files() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for (file, modified) in [
+            ("a.mp4", "2024-01-01"),
+            ("b.mp4", "2024-06-01"),
+            ("c.mp4", "2024-06-02T08:00:00Z"),
+        ] {
+            let path = source.path().join(file);
+            fs::write(&path, "").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(parse_since(modified).unwrap())
+                .unwrap();
+        }
+
+        let options = LinkOptions {
+            modified_since: Some(parse_since("2024-06-01T00:00:00Z").unwrap()),
+            ..Default::default()
+        };
+        let summary =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+        assert_eq!(summary.created.len(), 2);
+        assert!(destination.path().join("S01E01 - b.mp4").exists());
+        assert!(destination.path().join("S01E02 - c.mp4").exists());
+    }
+
+    #[test]
+    fn test_dry_run_records_the_planned_actions() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let links = destination.path().join("Links");
+
+        let options = LinkOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let summary =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(links.display().to_string(), &options)
+                .unwrap();
+
+        let link = PlannedAction::Symlink {
+            source: source.path().join("a.mp4"),
+            target: links.join("S01E01 - a.mp4"),
+            replace: false,
+        };
+        assert_eq!(
+            summary.planned,
+            [
+                PlannedAction::CreateDir {
+                    target: links.clone()
+                },
+                link.clone()
+            ]
+        );
+        assert!(!links.exists());
+        assert_eq!(
+            serde_json::to_value(&link).unwrap(),
+            serde_json::json!({
+                "action": "symlink",
+                "source": source.path().join("a.mp4"),
+                "target": links.join("S01E01 - a.mp4"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_plan_then_apply() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mkv"), "").unwrap();
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let options = LinkOptions {
+            extensions: vec![".mkv".to_string()],
+            grouping: GroupingType::Plex,
+            write_nfo: true,
+            ..Default::default()
+        };
+
+        let plan = tree.plan(destination.path(), &options).unwrap();
+        let season = destination.path().join("Season 01");
+        let link = season.join(format!("{} - S01E01.mkv", tree.name()));
+        assert_eq!(
+            plan.iter()
+                .map(|action| action.target())
+                .collect::<Vec<_>>(),
+            [season.as_path(), &link, &link.with_extension("nfo")]
+        );
+        assert!(!season.exists());
+
+        let report = apply(&plan);
+        assert!(report.errors.is_empty());
+        assert_eq!((report.directories, report.created.len()), (1, 1));
+        assert!(link.is_file() && link.with_extension("nfo").is_file());
+
+        assert!(matches!(
+            tree.plan(destination.path(), &options).unwrap()[..],
+            [PlannedAction::WriteNfo { .. }]
+        ));
+        assert!(matches!(
+            tree.plan(source.path().join("Links"), &options),
+            Err(FileTreeError::OverlappingPaths { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sort_groups_by_decides_the_season_numbers() {
+        let source = tempfile::tempdir().unwrap();
+        for file in ["A/a1.mp4", "A/a2.mp4", "B/b.mp4"] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let old = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        fs::File::options()
+            .write(true)
+            .open(source.path().join("B/b.mp4"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let season_of_b = |group_sort| {
+            let destination = tempfile::tempdir().unwrap();
+            let options = LinkOptions {
+                group_sort,
+                ..Default::default()
+            };
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+            let names: Vec<String> = fs::read_dir(destination.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .filter(|name| name.ends_with(" - b.mp4"))
+                .collect();
+            names[0][..3].to_string()
+        };
+
+        assert_eq!(season_of_b(GroupSort::Name), "S02");
+        assert_eq!(season_of_b(GroupSort::Count), "S01");
+        assert_eq!(season_of_b(GroupSort::Mtime), "S01");
+    }
+
+    #[test]
+    fn test_apply_reports_progress_after_each_action() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        fs::write(source.path().join("b.mp4"), "").unwrap();
+        let links = destination.path().join("Links");
+        let tree = FileTree::from_directory(source.path().display().to_string(), None, &[], false);
+        let plan = tree.plan(&links, &LinkOptions::default()).unwrap();
+
+        let mut reported = Vec::new();
+        apply_with_progress(&plan, &mut |progress| {
+            reported.push((
+                progress.completed,
+                progress.total,
+                progress.current_path.to_path_buf(),
+            ))
+        });
+
+        assert_eq!(
+            reported,
+            [
+                (1, 3, links.clone()),
+                (2, 3, links.join("S01E01 - a.mp4")),
+                (3, 3, links.join("S01E02 - b.mp4")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_regex_ignores_case() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["Naruto - a.mp4", "NARUTO - b.mp4", "Bleach - c.mp4"] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let options = LinkOptions {
+            group_by: GroupBy::Regex(r"^(?P<group>\w+) -".to_string()),
+            group_template: Some("{name}".to_string()),
+            ..Default::default()
+        };
+        FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+            .create_grouped_symlinks(destination.path().display().to_string(), &options)
+            .unwrap();
+
+        for link in [
+            "Bleach/S01E01 - Bleach - c.mp4",
+            "NARUTO/S02E01 - NARUTO - b.mp4",
+            "NARUTO/S02E02 - Naruto - a.mp4",
+        ] {
+            assert!(
+                destination.path().join(link).is_file(),
+                "{} is missing",
+                link
+            );
+        }
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn test_group_by_regex_ignores_accents() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        for file in ["Pokémon - a.mp4", "Pokemon - b.mp4"] {
+            fs::write(source.path().join(file), "").unwrap();
+        }
+
+        let options = LinkOptions {
+            group_by: GroupBy::Regex(r"^(?P<group>\w+) -".to_string()),
+            ..Default::default()
+        };
+        let summary =
+            FileTree::from_directory(source.path().display().to_string(), None, &[], false)
+                .create_grouped_symlinks(destination.path().display().to_string(), &options)
+                .unwrap();
+
+        assert_eq!(summary.created.len(), 2);
+        assert!(destination.path().join("S01E01 - Pokemon - b.mp4").exists());
+        assert!(destination.path().join("S01E02 - Pokémon - a.mp4").exists());
+    }
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "yaml")]
+
+#[cfg(test)]
+mod tests {
+    use file_tree::FileTree;
+    use std::fs;
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("Season 1")).unwrap();
+        fs::write(root.path().join("Season 1").join("a.mp4"), "abc").unwrap();
+        fs::write(root.path().join("b.mkv"), "").unwrap();
+
+        let tree = FileTree::from_directory(root.path().display().to_string(), None, &[], false);
+        let yaml = tree.to_yaml().unwrap();
+        let reloaded: FileTree = serde_yaml::from_str(&yaml).unwrap();
+
+        assert_eq!(reloaded.to_json().unwrap(), tree.to_json().unwrap());
+        assert_eq!(reloaded.directories[0].sizes, [3]);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::GroupByArg;
+use anyhow::{Context, Result};
+use file_tree::{GroupSort, LinkMode, ScanConfig, SortOrder};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Name of the config file looked up in the current directory
+pub const DEFAULT_CONFIG: &str = "rip.toml";
+
+/// Defaults of the `sym-link` subcommand, read from a TOML file. Flags given on
+/// the command line take precedence; `exclude` and `include` patterns add up.
+///
+/// ```toml
+/// destination = "/media/Links/Show"
+/// extensions = [".mkv", ".srt"]
+/// link-mode = "hardlink"
+/// group-by = "filename"
+///
+/// [scan]
+/// exclude = ["**/Extras/**"]
+/// max-depth = 2
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub destination: Option<String>,
+    pub extensions: Vec<String>,
+    pub companion_extensions: Vec<String>,
+    pub specials_keywords: Vec<String>,
+    pub use_plex_folder_structure: bool,
+    pub group_by: Option<GroupByArg>,
+    pub group_by_regex: Option<String>,
+    pub sort_groups_by: Option<GroupSort>,
+    pub sort_order: Option<SortOrder>,
+    pub link_mode: Option<LinkMode>,
+    pub relative: bool,
+    pub lowercase_extensions: bool,
+    pub scan: ScanConfig,
+}
+
+impl ConfigFile {
+    /// Reads the config at `path`, or `rip.toml` in the current directory when it
+    /// exists. Without either, every setting has its default.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG).is_file() => Path::new(DEFAULT_CONFIG),
+            None => return Ok(Self::default()),
+        };
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("invalid config {}", path.display()))
+    }
+}
@@ -1,5 +1,20 @@
-use clap::{Parser, Subcommand};
-use file_tree::FileTree;
+mod config;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use config::ConfigFile;
+use file_tree::{
+    check_disjoint, expand_destination, FileTree, FileTreeError, GroupBy, GroupSort, GroupingType,
+    LinkMode, LinkOptions, Progress, RenamePattern, ScanConfig, SortOrder, SymlinkReport,
+    DEFAULT_SPECIALS_KEYWORDS,
+};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
 
 #[derive(Parser)]
 #[command(
@@ -10,31 +25,1002 @@ use file_tree::FileTree;
 struct Args {
     #[command(subcommand)]
     action: Action,
+
+    /// Maximum number of filesystem operations in flight at once, e.g. 2 to 4 for a NAS
+    /// throttling concurrent requests (0 = unlimited, scans then issue one per thread)
+    #[arg(long, global = true, default_value_t = 0)]
+    max_concurrent_io: usize,
+
+    /// Threads scanning directories (0 = one per CPU, 1 = one directory at a time)
+    /// [default: threads of the [scan] table of the config file, or 0]
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
+    /// Abort a scan finding more than this many files, e.g. when pointed at / by mistake
+    #[arg(long, global = true)]
+    max_files: Option<usize>,
+
+    /// Log each operation (-v) or every detail (-vv); RUST_LOG takes precedence
+    #[arg(long, short = 'v', global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// TOML file holding defaults for sym-link's flags [default: ./rip.toml when present]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Only print the results, without banners, summaries or progress
+    #[arg(long, short = 'q', global = true, conflicts_with = "json_output")]
+    quiet: bool,
+
+    /// Print the outcome of the subcommand as a single JSON object (one line per run for watch)
+    #[arg(long, global = true)]
+    json_output: bool,
+}
+
+impl Args {
+    // The scan options shared by every subcommand
+    fn scan_config(&self) -> ScanConfig {
+        with_scan_flags(ScanConfig::new(), self.threads, self.max_files)
+    }
+
+    fn output(&self) -> Output {
+        match (self.quiet, self.json_output) {
+            (_, true) => Output::Json,
+            (true, false) => Output::Quiet,
+            (false, false) => Output::Human,
+        }
+    }
 }
 
+// Parsed once per run, so the size of the sym-link variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Action {
     #[command(name = "sym-link")]
     SymLink {
+        /// Directory to link (repeatable: the trees of several directories, e.g. a show
+        /// split over drives, are merged and linked as one)
+        #[arg(long, short = 'd', required_unless_present_any = ["tree_json", "from_stdin"])]
+        path_to_base_dir: Vec<String>,
+
+        /// Link the files of a tree saved by the `json` subcommand instead of scanning a directory
+        #[arg(long, conflicts_with = "path_to_base_dir")]
+        tree_json: Option<String>,
+
+        /// Link the files listed on stdin, one path per line, instead of scanning a directory
+        #[arg(long, conflicts_with_all = ["path_to_base_dir", "tree_json"])]
+        from_stdin: bool,
+
+        /// Directory in which the links are created (or `destination` in the config file),
+        /// with the placeholders `{source}`, `{show}` and `{date}`, e.g. `{source}/../_plex/{show}`
+        #[arg(long, short = 'f')]
+        path_to_destination: Option<String>,
+
+        /// Media file extension to link (repeatable, case-insensitive, defaults to .mp4)
+        #[arg(long = "extension", short = 'e')]
+        extensions: Vec<String>,
+
+        /// Extension of the files named after an episode to link along with it, e.g. .jpg or .nfo (repeatable)
+        #[arg(long = "companion-extension")]
+        companion_extensions: Vec<String>,
+
+        /// Print the directories and links that would be created without touching the filesystem
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the dry run's planned actions as text or as a JSON array
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, requires = "dry_run")]
+        output_format: OutputFormat,
+
+        /// Maximum number of directory levels to descend below the base directory
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// Create `Season xx/<show> - SxxEyy.<ext>` links that Plex's TV agent recognizes
+        #[arg(long)]
+        use_plex_folder_structure: bool,
+
+        /// Make a season of every directory holding media files or of every file [default: parent-dir]
+        #[arg(long, value_enum)]
+        group_by: Option<GroupByArg>,
+
+        /// Make a season of the files whose name yields the same `(?P<group>...)` capture
+        #[arg(long, conflicts_with = "group_by")]
+        group_by_regex: Option<String>,
+
+        /// Order of the seasons, which decides their numbers [default: name]
+        #[arg(long, value_enum)]
+        sort_groups_by: Option<GroupSort>,
+
+        /// Name of each season's directory, e.g. `Season {index:02}` or `{type} {index} - {name}`
+        #[arg(long)]
+        group_template: Option<String>,
+
+        /// Skip media files smaller than this size, e.g. `50MB` for samples
+        #[arg(long, value_parser = file_tree::parse_size)]
+        min_size: Option<u64>,
+
+        /// Skip media files last modified before this UTC date or time ago, e.g. `2024-05-01`,
+        /// `2024-05-01T18:30:00Z` or `7d`, to only link new files
+        #[arg(long, value_name = "DATE", value_parser = file_tree::parse_since)]
+        since: Option<SystemTime>,
+
+        /// Number of the first season, e.g. to continue the seasons already in a library [default: 1]
+        #[arg(long, conflicts_with_all = ["flatten", "mirror"])]
+        season_start: Option<u32>,
+
+        /// Number of the first episode of the first season [default: 1]
+        #[arg(long, conflicts_with_all = ["flatten", "mirror"])]
+        episode_start: Option<u32>,
+
+        /// Number specials (`Special`, `OVA`, `Extra`, a `Specials` directory, ...) in season 0
+        #[arg(long, conflicts_with_all = ["flatten", "mirror"])]
+        specials: bool,
+
+        /// Word marking a special, replacing the default ones (repeatable, implies --specials)
+        #[arg(long = "specials-keyword", conflicts_with_all = ["flatten", "mirror"])]
+        specials_keywords: Vec<String>,
+
+        /// Rewrite of the link names as `regex=>replacement`, e.g. `\[[^]]*\] *=>` to strip tags (repeatable)
+        #[arg(long = "rename-pattern", value_name = "REGEX=>REPLACEMENT")]
+        rename_patterns: Vec<RenamePattern>,
+
+        /// Lowercase the extension of the links (`Pilot.MKV` is linked as `Pilot.mkv`)
+        #[arg(long)]
+        lowercase_extensions: bool,
+
+        /// Link every media file straight into the destination as `0001 - <name>`, `0002 - <name>`, ...
+        #[arg(long, conflicts_with_all = ["use_plex_folder_structure", "group_by_regex", "group_template"])]
+        flatten: bool,
+
+        /// Recreate the source directories in the destination and link each media file at the same place
+        #[arg(long, conflicts_with_all = ["use_plex_folder_structure", "group_by_regex", "group_template", "flatten"])]
+        mirror: bool,
+
+        /// Order deciding the season and episode numbers [default: natural]
+        #[arg(long, value_enum)]
+        sort_order: Option<SortOrder>,
+
+        /// Create symbolic links, hard links (which cannot cross devices) or copies [default: symlink]
+        #[arg(long, value_enum)]
+        link_mode: Option<LinkMode>,
+
+        /// Link each season directory as a whole instead of its files, e.g. for sources already laid out for Plex
+        #[arg(long, overrides_with = "symlink_file", conflicts_with_all = ["flatten", "mirror", "copy", "write_nfo"])]
+        symlink_dir: bool,
+
+        /// Link the media files one by one (the default, cancels --symlink-dir)
+        #[arg(long, overrides_with = "symlink_dir")]
+        symlink_file: bool,
+
+        /// Check each new link resolves to its source, and hash each copy against its source
+        #[arg(long)]
+        verify: bool,
+
+        /// Write a CSV mapping each linked media file to its link, season and episode
+        #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+        csv: Option<PathBuf>,
+
+        /// Fail before linking anything when a file of the tree no longer exists, instead of skipping it
+        #[arg(long)]
+        strict: bool,
+
+        /// Delete a regular file found where a link goes and link in its place, instead of reporting a conflict
+        #[arg(long)]
+        force: bool,
+
+        /// Copy the files instead of linking them, e.g. onto a FAT32 drive (same as --link-mode copy)
+        #[arg(long, conflicts_with_all = ["link_mode", "relative"])]
+        copy: bool,
+
+        /// Store source paths relative to the links instead of absolute paths
+        #[arg(long)]
+        relative: bool,
+
+        /// Write an episode .nfo file next to each link (with --use-plex-folder-structure)
+        #[arg(long)]
+        write_nfo: bool,
+
+        /// Only link the first of the media files holding the same content
+        #[arg(long)]
+        dedupe: bool,
+
+        /// Name marker of the preferred quality of an episode, e.g. `1080p` or `.mkv` (repeatable, best first)
+        #[arg(long = "prefer", value_name = "MARKER")]
+        quality_priority: Vec<String>,
+
+        /// Glob matched against full paths to skip while indexing (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Glob matched against full paths of the files to keep (repeatable, excludes win)
+        #[arg(long)]
+        include: Vec<String>,
+
+        /// Also index dotfiles and NAS/OS junk such as @eaDir and .AppleDouble
+        #[arg(long)]
+        include_hidden: bool,
+
+        /// Follow symbolic links found in the base directory instead of skipping them
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Give up on directories that cannot be read within this many seconds, e.g. on a
+        /// hung network mount; they are left out and reported as failures
+        #[arg(long, value_name = "SECONDS")]
+        scan_timeout: Option<u64>,
+    },
+
+    /// Link the base directory, then link again whenever files change below it
+    #[command(name = "watch")]
+    Watch {
         #[arg(long, short = 'd', required = true)]
         path_to_base_dir: String,
 
-        #[arg(long, short = 'f', required = false)]
+        #[arg(long, short = 'f', required = true)]
         path_to_destination: String,
+
+        /// Media file extension to link (repeatable, case-insensitive, defaults to .mp4)
+        #[arg(long = "extension", short = 'e')]
+        extensions: Vec<String>,
+
+        /// Create `Season xx/<show> - SxxEyy.<ext>` links that Plex's TV agent recognizes
+        #[arg(long)]
+        use_plex_folder_structure: bool,
+
+        /// Create symbolic links, hard links (which cannot cross devices) or copies
+        #[arg(long, value_enum, default_value_t = LinkMode::Symlink)]
+        link_mode: LinkMode,
+
+        /// Store source paths relative to the links instead of absolute paths
+        #[arg(long)]
+        relative: bool,
+
+        /// Milliseconds without changes to wait for before linking again
+        #[arg(long, default_value_t = 2000)]
+        debounce_ms: u64,
+    },
+
+    /// Print the tree of the base directory
+    #[command(name = "tree")]
+    Tree {
+        #[arg(long, short = 'd', required = true)]
+        path_to_base_dir: String,
+
+        /// Maximum number of directory levels to descend below the base directory
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// Show the size of every file
+        #[arg(long, conflicts_with = "format")]
+        sizes: bool,
+
+        /// Leave out the directories holding no file at any depth
+        #[arg(long)]
+        prune_empty: bool,
+
+        /// Draw the tree as text, print it as a nested Markdown list or as a Graphviz
+        /// digraph (e.g. piped to `dot -Tpng -o tree.png`)
+        #[arg(long, value_enum, default_value_t = TreeFormat::Text)]
+        format: TreeFormat,
+    },
+
+    /// Dump the tree of the base directory as JSON
+    #[command(name = "json")]
+    Json {
+        #[arg(long, short = 'd', required = true)]
+        path_to_base_dir: String,
+
+        /// File to write the JSON to instead of stdout
+        #[arg(long, short = 'o')]
+        output: Option<String>,
+
+        /// Pretty-print the JSON
+        #[arg(long)]
+        pretty: bool,
+
+        /// Serialization format of the dump
+        #[arg(long, value_enum, default_value_t = DumpFormat::Json)]
+        format: DumpFormat,
+    },
+
+    /// Print the files added to and removed from the base directory since a tree was
+    /// saved by the `json` subcommand
+    #[command(name = "diff")]
+    Diff {
+        /// Tree saved by the `json` subcommand
+        #[arg(long, required = true)]
+        tree_json: String,
+
+        #[arg(long, short = 'd', visible_alias = "path", required = true)]
+        path_to_base_dir: String,
+    },
+
+    /// Print every file of the base directory on its own line
+    #[command(name = "list")]
+    List {
+        #[arg(long, short = 'd', required = true)]
+        path_to_base_dir: String,
+
+        /// String prepended to every printed path
+        #[arg(long, short = 'p', default_value = "")]
+        prefix: String,
+    },
+
+    /// Write an M3U playlist of the media files of the base directory
+    #[command(name = "playlist")]
+    Playlist {
+        #[arg(long, short = 'd', required = true)]
+        path_to_base_dir: String,
+
+        /// Path of the .m3u8 file to write
+        #[arg(long, short = 'o', required = true)]
+        output: String,
+
+        /// Media file extension to include (repeatable, case-insensitive, defaults to .mp4)
+        #[arg(long = "extension", short = 'e')]
+        extensions: Vec<String>,
+
+        /// Write paths relative to the playlist instead of absolute paths
+        #[arg(long)]
+        relative: bool,
+    },
+
+    /// Remove the links (and the directories left empty) below a destination
+    #[command(name = "clean")]
+    Clean {
+        #[arg(long, short = 'f', required = true)]
+        destination: String,
+
+        /// Only remove links whose target no longer exists
+        #[arg(long)]
+        dangling_only: bool,
     },
 }
 
-fn main() {
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum GroupByArg {
+    Filename,
+    ParentDir,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+// How the subcommands print their outcome
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Human,
+    Quiet,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpFormat {
+    Json,
+    Yaml,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TreeFormat {
+    Text,
+    Markdown,
+    Dot,
+}
+
+// Applies the global scan flags that were given over `config`, keeping its
+// settings (e.g. the [scan] table of the config file) for the others
+fn with_scan_flags(
+    config: ScanConfig,
+    threads: Option<usize>,
+    max_files: Option<usize>,
+) -> ScanConfig {
+    let config = match threads {
+        Some(threads) => config.threads(threads),
+        None => config,
+    };
+    match max_files {
+        Some(max_files) => config.max_files(max_files),
+        None => config,
+    }
+}
+
+fn main() -> Result<()> {
     let args = Args::parse();
+    init_logger(&args);
+    file_tree::set_max_concurrent_io(args.max_concurrent_io);
+    let output = args.output();
+    let scan_config = args.scan_config();
 
     match args.action {
         Action::SymLink {
             path_to_base_dir,
+            tree_json,
+            from_stdin,
             path_to_destination,
+            extensions,
+            companion_extensions,
+            dry_run,
+            output_format,
+            max_depth,
+            use_plex_folder_structure,
+            group_by,
+            group_by_regex,
+            sort_groups_by,
+            group_template,
+            min_size,
+            since,
+            season_start,
+            episode_start,
+            specials,
+            specials_keywords,
+            rename_patterns,
+            lowercase_extensions,
+            flatten,
+            mirror,
+            sort_order,
+            link_mode,
+            symlink_dir,
+            symlink_file: _,
+            verify,
+            csv,
+            strict,
+            force,
+            copy,
+            relative,
+            write_nfo,
+            dedupe,
+            quality_priority,
+            exclude,
+            include,
+            include_hidden,
+            follow_symlinks,
+            scan_timeout,
         } => {
-            println!("SymLinking {} to {}", path_to_base_dir, path_to_destination);
-            let file_tree = FileTree::from_directory(path_to_base_dir.to_string(), 1);
-            file_tree.create_grouped_symlinks(path_to_destination.to_string());
+            let file = ConfigFile::load(args.config.as_deref())?;
+            let destination_template =
+                path_to_destination.or(file.destination).ok_or_else(|| {
+                    anyhow!("no destination given, pass -f or set `destination` in the config file")
+                })?;
+            let grouping = if use_plex_folder_structure || file.use_plex_folder_structure {
+                GroupingType::Plex
+            } else {
+                GroupingType::Original
+            };
+            // A grouping given on the command line replaces the one of the file
+            let (group_by_regex, group_by) = match (group_by_regex, group_by) {
+                (None, None) => (file.group_by_regex, file.group_by),
+                flags => flags,
+            };
+            let group_by = match (group_by_regex, group_by.unwrap_or(GroupByArg::ParentDir)) {
+                (Some(pattern), _) => GroupBy::Regex(pattern),
+                (None, GroupByArg::Filename) => GroupBy::Filename,
+                (None, GroupByArg::ParentDir) => GroupBy::ParentDir,
+            };
+            let options = LinkOptions {
+                extensions: if extensions.is_empty() {
+                    file.extensions
+                } else {
+                    extensions
+                },
+                companion_extensions: if companion_extensions.is_empty() {
+                    file.companion_extensions
+                } else {
+                    companion_extensions
+                },
+                grouping,
+                group_by,
+                group_sort: sort_groups_by.or(file.sort_groups_by).unwrap_or_default(),
+                group_template,
+                sort_order: sort_order.or(file.sort_order).unwrap_or_default(),
+                link_mode: link_mode
+                    .or(copy.then_some(LinkMode::Copy))
+                    .or(file.link_mode)
+                    .unwrap_or_default(),
+                relative: relative || file.relative,
+                write_nfo,
+                dry_run,
+                dedupe,
+                quality_priority,
+                min_size,
+                modified_since: since,
+                season_start,
+                episode_start,
+                specials: match (specials_keywords, file.specials_keywords) {
+                    (keywords, _) if !keywords.is_empty() => keywords,
+                    (_, keywords) if !keywords.is_empty() => keywords,
+                    _ if specials => DEFAULT_SPECIALS_KEYWORDS.map(String::from).to_vec(),
+                    _ => Vec::new(),
+                },
+                flatten,
+                mirror,
+                rename_patterns,
+                normalize_extension_case: lowercase_extensions || file.lowercase_extensions,
+                link_directories: symlink_dir,
+                verify,
+                strict,
+                force,
+            };
+            // Checked before scanning so a bad pattern or template fails fast
+            options.validate()?;
+
+            // The destination is resolved against the source before anything is created
+            let destination_for = |source: &str| -> Result<String> {
+                let destination = expand_destination(&destination_template, source)?;
+                check_disjoint(Path::new(source), Path::new(&destination))?;
+                Ok(destination)
+            };
+            let (file_tree, path_to_destination, scan_errors) = match tree_json {
+                _ if from_stdin => {
+                    let paths = io::stdin()
+                        .lines()
+                        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+                        .collect::<io::Result<Vec<String>>>()?;
+                    let file_tree = FileTree::from_string_vector(paths)?;
+                    let destination = destination_for(&file_tree.path)?;
+                    let scan_errors = Vec::new();
+                    (file_tree, destination, scan_errors)
+                }
+                Some(tree_json) => {
+                    let file_tree = FileTree::from_json(&fs::read_to_string(tree_json)?)?;
+                    let destination = destination_for(&file_tree.path)?;
+                    let scan_errors = Vec::new();
+                    (file_tree, destination, scan_errors)
+                }
+                None => {
+                    // The first directory names the show and anchors the destination
+                    let destination = destination_for(&path_to_base_dir[0])?;
+                    for root in &path_to_base_dir[1..] {
+                        check_disjoint(Path::new(root), Path::new(&destination))?;
+                    }
+                    let mut config = file.scan.exclude(exclude).include(include);
+                    if include_hidden {
+                        config = config.include_hidden(true);
+                    }
+                    if follow_symlinks {
+                        config = config.follow_symlinks(true);
+                    }
+                    if let Some(max_depth) = max_depth {
+                        config = config.max_depth(max_depth);
+                    }
+                    if let Some(scan_timeout) = scan_timeout {
+                        config = config.scan_timeout(Duration::from_secs(scan_timeout));
+                    }
+                    let config = with_scan_flags(config, args.threads, args.max_files);
+                    let mut scan_errors = Vec::new();
+                    let mut file_tree = None;
+                    for root in path_to_base_dir {
+                        let (tree, errors) = config.build_from_with_errors(root)?;
+                        scan_errors.extend(errors);
+                        file_tree = Some(match file_tree {
+                            Some(merged) => FileTree::merge(merged, tree),
+                            None => tree,
+                        });
+                    }
+                    let file_tree =
+                        file_tree.expect("clap requires a base directory, a tree or stdin");
+                    (file_tree, destination, scan_errors)
+                }
+            };
+
+            if output_format == OutputFormat::Json && output != Output::Json {
+                let report = file_tree.create_grouped_symlinks(path_to_destination, &options)?;
+                println!("{}", serde_json::to_string_pretty(&report.planned)?);
+                return Ok(());
+            }
+
+            if output == Output::Human {
+                println!("SymLinking {} to {}", file_tree.path, path_to_destination);
+            }
+            let show_progress =
+                output == Output::Human && args.verbose == 0 && io::stderr().is_terminal();
+            let mut report = file_tree.create_grouped_symlinks_with_progress(
+                path_to_destination.clone(),
+                &options,
+                &mut |progress| {
+                    if show_progress {
+                        print_progress(progress)
+                    }
+                },
+            )?;
+            // Directories the scan gave up on count as failures of the run
+            report.failures.extend(scan_errors);
+
+            match output {
+                Output::Human => print_report(&report, dry_run),
+                Output::Quiet => {}
+                Output::Json => print_json(&report_json(
+                    &report,
+                    &file_tree.path,
+                    &path_to_destination,
+                    dry_run,
+                ))?,
+            }
+            if let Some(csv) = csv {
+                fs::write(&csv, report.to_csv())
+                    .with_context(|| format!("failed to write {}", csv.display()))?;
+            }
+            if !report.is_success() {
+                return Err(anyhow!(
+                    "{} links could not be created",
+                    report.failures.len() + report.conflicts.len()
+                ));
+            }
         }
+        Action::Watch {
+            path_to_base_dir,
+            path_to_destination,
+            extensions,
+            use_plex_folder_structure,
+            link_mode,
+            relative,
+            debounce_ms,
+        } => {
+            let grouping = if use_plex_folder_structure {
+                GroupingType::Plex
+            } else {
+                GroupingType::Original
+            };
+            let options = LinkOptions {
+                extensions,
+                grouping,
+                link_mode,
+                relative,
+                ..Default::default()
+            };
+            watch(
+                &path_to_base_dir,
+                &path_to_destination,
+                &options,
+                Duration::from_millis(debounce_ms),
+                &scan_config,
+                output,
+            )?;
+        }
+        Action::Tree {
+            path_to_base_dir,
+            max_depth,
+            sizes,
+            prune_empty,
+            format,
+        } => {
+            let mut file_tree = scan(path_to_base_dir, max_depth, &scan_config)?;
+            if prune_empty {
+                file_tree.prune_empty();
+            }
+            if output == Output::Json {
+                print_json(&serde_json::json!({
+                    "tree": file_tree,
+                    "stats": file_tree.stats(),
+                }))?;
+                return Ok(());
+            }
+            // Both are meant to be used as is, without the stats
+            match format {
+                TreeFormat::Markdown => {
+                    print!("{}", file_tree.to_markdown());
+                    return Ok(());
+                }
+                TreeFormat::Dot => {
+                    print!("{}", file_tree.to_dot());
+                    return Ok(());
+                }
+                TreeFormat::Text => {}
+            }
+            if sizes {
+                print!("{}", file_tree.to_file_tree_with_sizes(true));
+            } else {
+                print!("{:#}", file_tree);
+            }
+            if output == Output::Human {
+                println!("\n{}", file_tree.stats());
+            }
+        }
+        Action::Json {
+            path_to_base_dir,
+            output: path,
+            pretty,
+            format,
+        } => {
+            // Without an output file the dump is printed, which would not be JSON
+            if output == Output::Json && path.is_none() && matches!(format, DumpFormat::Yaml) {
+                return Err(anyhow!(
+                    "--json-output cannot print a YAML dump, write it to a file with -o"
+                ));
+            }
+            let file_tree = scan(path_to_base_dir, None, &scan_config)?;
+            let dump = match format {
+                DumpFormat::Json if pretty => serde_json::to_string_pretty(&file_tree)?,
+                DumpFormat::Json => file_tree.to_json()?,
+                DumpFormat::Yaml => file_tree.to_yaml()?,
+            };
+
+            // Without an output file the dump itself is the outcome
+            match path {
+                Some(path) => {
+                    fs::write(&path, dump)?;
+                    if output == Output::Json {
+                        print_json(&serde_json::json!({
+                            "output": path,
+                            "stats": file_tree.stats(),
+                        }))?;
+                    }
+                }
+                None => println!("{}", dump),
+            }
+        }
+        Action::Diff {
+            tree_json,
+            path_to_base_dir,
+        } => {
+            let old = FileTree::from_json(
+                &fs::read_to_string(&tree_json)
+                    .with_context(|| format!("failed to read {}", tree_json))?,
+            )?;
+            let new = scan(path_to_base_dir, None, &scan_config)?;
+            let changes = file_tree::diff(&old, &new);
+            if output == Output::Json {
+                print_json(&changes)?;
+                return Ok(());
+            }
+            for file in &changes.added {
+                println!("+ {}", file);
+            }
+            for file in &changes.removed {
+                println!("- {}", file);
+            }
+            // The summary goes to stderr so stdout stays a plain list of changes
+            if output == Output::Human {
+                eprintln!("{}", changes);
+            }
+        }
+        Action::List {
+            path_to_base_dir,
+            prefix,
+        } => {
+            let file_tree = scan(path_to_base_dir, None, &scan_config)?;
+            if output == Output::Json {
+                print_json(&serde_json::json!({
+                    "files": file_tree.to_file_list(&prefix),
+                    "stats": file_tree.stats(),
+                }))?;
+                return Ok(());
+            }
+            for file in file_tree.to_file_list(&prefix) {
+                println!("{}", file);
+            }
+            // The summary goes to stderr so stdout stays a plain list of paths
+            if output == Output::Human {
+                eprintln!("{}", file_tree.stats());
+            }
+        }
+        Action::Playlist {
+            path_to_base_dir,
+            output: path,
+            extensions,
+            relative,
+        } => {
+            let file_tree = scan(path_to_base_dir, None, &scan_config)?;
+            let playlist_directory = Path::new(&path).parent().filter(|_| relative);
+            let playlist = file_tree.to_m3u(&extensions, playlist_directory);
+            fs::write(&path, &playlist)?;
+            if output == Output::Json {
+                let entries = playlist
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .count();
+                print_json(&serde_json::json!({ "output": path, "entries": entries }))?;
+            }
+        }
+        Action::Clean {
+            destination,
+            dangling_only,
+        } => {
+            let summary = file_tree::clean_links(Path::new(&destination), dangling_only)?;
+            match output {
+                Output::Human => println!(
+                    "Removed {} links and {} directories",
+                    summary.links, summary.directories
+                ),
+                Output::Quiet => {}
+                Output::Json => print_json(&summary)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Warnings and errors are logged by default, dry runs log their planned operations
+fn init_logger(args: &Args) {
+    let dry_run = matches!(args.action, Action::SymLink { dry_run: true, .. });
+    let level = match args.verbose {
+        0 if dry_run => log::LevelFilter::Info,
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Info,
+        _ => log::LevelFilter::Debug,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+}
+
+// Overwrites a single "Linking n/total" line on stderr
+fn print_progress(progress: Progress) {
+    eprint!("\rLinking {}/{}", progress.completed, progress.total);
+    if progress.completed == progress.total {
+        eprintln!();
+    }
+}
+
+// The failures themselves were logged as they happened
+fn print_report(report: &SymlinkReport, dry_run: bool) {
+    let verb = if dry_run { "would be" } else { "were" };
+    println!(
+        "{} directories and {} links {} created, {} skipped, {} replaced, {} failed",
+        report.directories,
+        report.created.len(),
+        verb,
+        report.skipped.len(),
+        report.replaced.len(),
+        report.failures.len()
+    );
+    if !report.missing.is_empty() {
+        println!(
+            "{} files no longer exist and were skipped",
+            report.missing.len()
+        );
+    }
+    if !report.conflicts.is_empty() {
+        println!(
+            "{} links were not created, a file or directory is in the way (see --force):",
+            report.conflicts.len()
+        );
+        for conflict in &report.conflicts {
+            println!("  {}", conflict.display());
+        }
+    }
+    if !report.verified.is_empty() {
+        println!("{} links verified", report.verified.len());
+    }
+}
+
+// The outcome of a sym-link run, as printed by --json-output
+fn report_json(
+    report: &SymlinkReport,
+    source: &str,
+    destination: &str,
+    dry_run: bool,
+) -> serde_json::Value {
+    serde_json::json!({
+        "source": source,
+        "destination": destination,
+        "dry_run": dry_run,
+        "success": report.is_success(),
+        "directories": report.directories,
+        "created": report.created,
+        "skipped": report.skipped,
+        "replaced": report.replaced,
+        "missing": report.missing,
+        "conflicts": report.conflicts,
+        "verified": report.verified,
+        "failures": report
+            .failures
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        "planned": report.planned,
+        "mappings": report.mappings,
+    })
+}
+
+// Prints `value` as JSON on a single line
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
+
+// Scans `path` like `FileTree::try_from_directory`, with the global options of `config`
+fn scan(
+    path: String,
+    max_depth: Option<usize>,
+    config: &ScanConfig,
+) -> Result<FileTree, FileTreeError> {
+    match max_depth {
+        Some(max_depth) => config.clone().max_depth(max_depth).build_from(path),
+        None => config.build_from(path),
+    }
+}
+
+// Links `base` into `destination`, then again after every burst of changes below
+// `base`. Existing links are skipped, so each run only adds the new files.
+fn watch(
+    base: &str,
+    destination: &str,
+    options: &LinkOptions,
+    debounce: Duration,
+    config: &ScanConfig,
+    output: Output,
+) -> Result<()> {
+    // Links created inside the watched directory would trigger another run
+    check_disjoint(Path::new(base), Path::new(destination))?;
+    let link = || match config.build_from(base) {
+        Ok(file_tree) => {
+            match file_tree.create_grouped_symlinks(destination.to_string(), options) {
+                Ok(report) => match output {
+                    Output::Human => print_report(&report, false),
+                    Output::Quiet => {}
+                    Output::Json => {
+                        let report = report_json(&report, base, destination, false);
+                        if let Err(error) = print_json(&report) {
+                            log::error!("{:#}", error);
+                        }
+                    }
+                },
+                Err(error) => log::error!("{:#}", anyhow::Error::from(error)),
+            }
+        }
+        // The source may be mid-copy, the next change triggers another run
+        Err(error) => log::error!("{:#}", anyhow::Error::from(error)),
+    };
+    link();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&fs::canonicalize(base)?, RecursiveMode::Recursive)?;
+    if output == Output::Human {
+        println!("Watching {} for changes", base);
+    }
+
+    // Reading the source must not trigger another run
+    let is_change = |event: notify::Result<Event>| match event {
+        Ok(event) => !matches!(event.kind, EventKind::Access(_)),
+        Err(error) => {
+            log::error!("Watch error: {}", error);
+            false
+        }
+    };
+
+    while let Ok(event) = receiver.recv() {
+        if !is_change(event) {
+            continue;
+        }
+        // Wait for the burst of changes (e.g. a copy in progress) to settle
+        while receiver.recv_timeout(debounce).is_ok() {}
+        link();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threads_of_config_file_are_kept_without_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rip.toml");
+        fs::write(&path, "[scan]\nthreads = 2\n").unwrap();
+        let file = ConfigFile::load(Some(&path)).unwrap();
+
+        let args = Args::try_parse_from(["rip", "sym-link", "-d", "/media/Show"]).unwrap();
+        let config = with_scan_flags(file.scan.clone(), args.threads, args.max_files);
+        assert_eq!(
+            format!("{:?}", config),
+            format!("{:?}", ScanConfig::new().threads(2))
+        );
+
+        let args = Args::try_parse_from(["rip", "--threads", "1", "sym-link", "-d", "/media/Show"]);
+        let args = args.unwrap();
+        let config = with_scan_flags(file.scan, args.threads, args.max_files);
+        assert_eq!(
+            format!("{:?}", config),
+            format!("{:?}", ScanConfig::new().threads(1))
+        );
     }
 }
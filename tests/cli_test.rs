@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use std::fs;
+    use std::path::Path;
+    use std::process::{Command, Output};
+
+    // Runs rip with `args`, without picking up a rip.toml from the current directory
+    fn rip(config: &Path, args: &[&str]) -> Output {
+        fs::write(config, "").unwrap();
+        Command::new(env!("CARGO_BIN_EXE_rust_indexer_for_plex"))
+            .arg("--config")
+            .arg(config)
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_json_output_of_sym_link_is_a_single_report() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let source_path = source.path().display().to_string();
+        let destination_path = destination.path().display().to_string();
+
+        let output = rip(
+            &destination.path().join("rip.toml"),
+            &[
+                "--json-output",
+                "sym-link",
+                "-d",
+                &source_path,
+                "-f",
+                &destination_path,
+                "--dry-run",
+            ],
+        );
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.lines().count(), 1);
+        let report: Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(report["source"], source_path.as_str());
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(report["success"], true);
+        assert_eq!(report["planned"][0]["action"], "symlink");
+        assert_eq!(report["created"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_json_output_of_json_names_the_output_file() {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let dump = work.path().join("tree.json").display().to_string();
+        let source_path = source.path().display().to_string();
+        let config = work.path().join("rip.toml");
+
+        let output = rip(
+            &config,
+            &["--json-output", "json", "-d", &source_path, "-o", &dump],
+        );
+        assert!(output.status.success());
+        let outcome: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(outcome["output"], dump.as_str());
+        assert!(outcome["stats"].is_object());
+
+        let output = rip(
+            &config,
+            &[
+                "--json-output",
+                "json",
+                "-d",
+                &source_path,
+                "--format",
+                "yaml",
+            ],
+        );
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+    }
+
+    #[test]
+    fn test_playlist_writes_relative_entries() {
+        let source = tempfile::tempdir().unwrap();
+        let show = source.path().join("Show");
+        fs::create_dir(&show).unwrap();
+        for file in ["Episode 10.mp4", "Episode 2.mp4", "notes.txt"] {
+            fs::write(show.join(file), "").unwrap();
+        }
+        let playlist = source.path().join("Show.m3u8");
+        let show_path = show.display().to_string();
+        let playlist_path = playlist.display().to_string();
+
+        let output = rip(
+            &source.path().join("rip.toml"),
+            &[
+                "--json-output",
+                "playlist",
+                "-d",
+                &show_path,
+                "-o",
+                &playlist_path,
+                "--relative",
+            ],
+        );
+        assert!(output.status.success());
+        let outcome: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(outcome["entries"], 2);
+        assert_eq!(
+            fs::read_to_string(&playlist).unwrap(),
+            "#EXTM3U\nShow/Episode 2.mp4\nShow/Episode 10.mp4\n"
+        );
+    }
+
+    #[test]
+    fn test_quiet_json_prints_only_the_tree() {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("a.mp4"), "").unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let source_path = source.path().display().to_string();
+
+        let output = rip(
+            &work.path().join("rip.toml"),
+            &["--quiet", "json", "-d", &source_path],
+        );
+        assert!(output.status.success());
+        let tree: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(tree["path"], source_path.as_str());
+        assert_eq!(tree["files"].as_array().unwrap().len(), 1);
+    }
+}